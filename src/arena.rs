@@ -0,0 +1,177 @@
+//! Storage of many sequences in a single growable buffer.
+
+use std::sync::Arc;
+
+use crate::{
+    base_trait::{to_offset, Offset},
+    CapacityError, MutableStringSequence, SharedStringSequence, StringSequenceRef,
+    StringSequenceView,
+};
+
+/// Handle of a sequence stored in a [`StringSequenceArena`], which stays valid after the arena is
+/// frozen into a [`FrozenArena`].
+///
+/// Resolving a handle with an arena other than the one which created it gives an unrelated
+/// sequence, or `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SeqId(usize);
+
+impl SeqId {
+    /// Ordinal of the sequence in its arena.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Appends sequences into shared text and boundary buffers, instead of allocating per sequence.
+///
+/// ```
+/// use strseq::{arena::StringSequenceArena, StringSequence};
+///
+/// let mut arena = StringSequenceArena::new();
+/// let a = arena.push(&StringSequence::parse_delimited("usr/bin", '/'));
+/// let b = arena.push_tokens(["etc", "hosts"]);
+/// assert_eq!(arena.get(a).unwrap().text(), "usrbin");
+///
+/// let frozen = arena.freeze();
+/// let (a, b) = (frozen.get(a).unwrap(), frozen.get(b).unwrap());
+/// assert_eq!(b, ["etc", "hosts"].into_iter().collect::<StringSequence>());
+/// drop(frozen);
+/// assert_eq!(a.text(), "usrbin");
+/// ```
+#[derive(Debug, Clone)]
+pub struct StringSequenceArena {
+    /// Tokens of every sequence.
+    tokens: MutableStringSequence,
+    /// First token of each sequence, followed by the number of tokens.
+    starts: Vec<Offset>,
+}
+
+impl Default for StringSequenceArena {
+    fn default() -> Self {
+        Self { tokens: MutableStringSequence::new(), starts: vec![0] }
+    }
+}
+
+impl StringSequenceArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create new instance with capacities of all sequences in total.
+    pub fn with_capacity(num_seqs: usize, num_tokens: usize, num_chars: usize) -> Self {
+        let mut starts = Vec::with_capacity(num_seqs + 1);
+        starts.push(0);
+        Self { tokens: MutableStringSequence::with_capacity(num_tokens, num_chars), starts }
+    }
+
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends a copy of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total text length or the number of tokens overflows [`Offset`].
+    pub fn push<T: StringSequenceView + ?Sized>(&mut self, seq: &T) -> SeqId {
+        self.push_tokens(seq.iter())
+    }
+
+    /// Fallible version of [`Self::push`]. Nothing is appended on failure.
+    pub fn try_push<T: StringSequenceView + ?Sized>(
+        &mut self,
+        seq: &T,
+    ) -> Result<SeqId, CapacityError> {
+        self.try_push_tokens(seq.iter())
+    }
+
+    /// Appends a sequence of given tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total text length or the number of tokens overflows [`Offset`].
+    pub fn push_tokens<T: AsRef<str>>(&mut self, tokens: impl IntoIterator<Item = T>) -> SeqId {
+        self.try_push_tokens(tokens).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::push_tokens`]. Nothing is appended on failure.
+    pub fn try_push_tokens<T: AsRef<str>>(
+        &mut self,
+        tokens: impl IntoIterator<Item = T>,
+    ) -> Result<SeqId, CapacityError> {
+        self.tokens.try_extend(tokens)?;
+
+        // `try_extend` already checked the number of tokens.
+        self.starts.push(to_offset(self.tokens.len()).unwrap());
+        Ok(SeqId(self.len() - 1))
+    }
+
+    /// Borrows the sequence of given handle.
+    pub fn get(&self, id: SeqId) -> Option<StringSequenceRef<'_>> {
+        let (start, end) = (*self.starts.get(id.0)?, *self.starts.get(id.0 + 1)?);
+        Some(self.tokens.slice_ref(start as usize..end as usize))
+    }
+
+    /// Handles of every sequence, in order of insertion.
+    pub fn ids(&self) -> impl ExactSizeIterator<Item = SeqId> + DoubleEndedIterator {
+        (0..self.len()).map(SeqId)
+    }
+
+    /// Removes every sequence, keeping allocated buffers. Handles returned earlier are
+    /// invalidated.
+    pub fn clear(&mut self) {
+        self.tokens.clear();
+        self.starts.truncate(1);
+    }
+
+    /// Moves every sequence into a single shared buffer, whose sequences are handed out as
+    /// [`SharedStringSequence`]s.
+    pub fn freeze(self) -> FrozenArena {
+        FrozenArena {
+            tokens: self.tokens.into_string_sequence().into(),
+            starts: self.starts.into(),
+        }
+    }
+}
+
+/// Immutable form of [`StringSequenceArena`], which is cheap to clone. Every sequence shares the
+/// same buffer, which is kept alive until every sequence handed out is dropped.
+#[derive(Debug, Clone)]
+pub struct FrozenArena {
+    tokens: SharedStringSequence,
+    starts: Arc<[Offset]>,
+}
+
+impl FrozenArena {
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sequence of given handle, sharing the buffer of the arena.
+    pub fn get(&self, id: SeqId) -> Option<SharedStringSequence> {
+        let (start, end) = (*self.starts.get(id.0)?, *self.starts.get(id.0 + 1)?);
+        Some(self.tokens.subsequence(start as usize..end as usize))
+    }
+
+    /// Borrows the sequence of given handle, without touching the reference count.
+    pub fn get_ref(&self, id: SeqId) -> Option<StringSequenceRef<'_>> {
+        let (start, end) = (*self.starts.get(id.0)?, *self.starts.get(id.0 + 1)?);
+        Some(self.tokens.slice_ref(start as usize..end as usize))
+    }
+
+    /// Handles of every sequence, in order of insertion.
+    pub fn ids(&self) -> impl ExactSizeIterator<Item = SeqId> + DoubleEndedIterator {
+        (0..self.len()).map(SeqId)
+    }
+}
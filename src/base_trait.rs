@@ -1,20 +1,58 @@
 use std::iter::*;
 use std::ops::*;
 
+use crate::search::{FindIter, Searcher};
+use crate::{delimited::DisplayWith, MutableStringSequence, StringSequenceRef};
+
+/* ----------------------------------------- Offset ----------------------------------------- */
+
+/// Integer type to store token boundaries, which limits maximum length of the text and the number
+/// of tokens of a single sequence.
+///
+/// Defaults to `u32`. Enable `offset-u16` feature for tiny keys, or `offset-u64` for sequences
+/// whose text exceed 4 GiB. If both are enabled, the wider one wins.
+///
+/// # Warning
+///
+/// These features aren't additive: they change this type for every crate in the dependency
+/// graph. Only enable them from a leaf binary, never from a library, or another crate relying on
+/// the default width may start to panic on valid input.
+#[cfg(not(any(feature = "offset-u16", feature = "offset-u64")))]
+pub type Offset = u32;
+
+/// Integer type to store token boundaries, narrowed by `offset-u16` feature.
+#[cfg(all(feature = "offset-u16", not(feature = "offset-u64")))]
+pub type Offset = u16;
+
+/// Integer type to store token boundaries, widened by `offset-u64` feature.
+#[cfg(feature = "offset-u64")]
+pub type Offset = u64;
+
+/// Converts a text length or a token count into [`Offset`], if it fits.
+pub(crate) fn to_offset(value: usize) -> Option<Offset> {
+    Offset::try_from(value).ok()
+}
+
 /* ---------------------------------------- Iterator ---------------------------------------- */
-// Reuses efficient algorithm from `std::slice::Iter`.
+// Reuses efficient algorithm from `std::slice::Windows`.
 
 #[derive(Debug, Clone)]
 pub struct StringSequenceIter<'a> {
     buffer: &'a [u8],
-    index: std::slice::Iter<'a, std::ops::Range<u32>>,
+    index: std::slice::Windows<'a, Offset>,
+}
+
+impl<'a> StringSequenceIter<'a> {
+    pub(crate) fn new(buffer: &'a [u8], index: &'a [Offset]) -> Self {
+        Self { buffer, index: index.windows(2) }
+    }
 }
 
 impl<'a> Iterator for StringSequenceIter<'a> {
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.index.next().cloned().map(|range| retr(self.buffer, range))
+        self.index.next().map(|bound| retr_bound(self.buffer, bound))
     }
 
     /* ------------------------------------- Forwarding ------------------------------------- */
@@ -32,7 +70,7 @@ impl<'a> Iterator for StringSequenceIter<'a> {
     }
 
     fn nth(&mut self, n: usize) -> Option<Self::Item> {
-        self.index.nth(n).cloned().map(|range| retr(self.buffer, range))
+        self.index.nth(n).map(|bound| retr_bound(self.buffer, bound))
     }
 
     fn fold<B, F>(self, init: B, mut f: F) -> B
@@ -40,17 +78,17 @@ impl<'a> Iterator for StringSequenceIter<'a> {
         Self: Sized,
         F: FnMut(B, Self::Item) -> B,
     {
-        self.index.fold(init, move |acc, range| f(acc, retr(self.buffer, range.clone())))
+        self.index.fold(init, move |acc, bound| f(acc, retr_bound(self.buffer, bound)))
     }
 }
 
 impl<'a> DoubleEndedIterator for StringSequenceIter<'a> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.index.next_back().cloned().map(|range| retr(self.buffer, range))
+        self.index.next_back().map(|bound| retr_bound(self.buffer, bound))
     }
 
     fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
-        self.index.nth_back(n).cloned().map(|range| retr(self.buffer, range))
+        self.index.nth_back(n).map(|bound| retr_bound(self.buffer, bound))
     }
 }
 
@@ -58,48 +96,253 @@ impl<'a> ExactSizeIterator for StringSequenceIter<'a> {}
 
 impl<'a> FusedIterator for StringSequenceIter<'a> {}
 
-/* ---------------------------------- Range32 To RangeUsize --------------------------------- */
+/* ---------------------------------- Offset To RangeUsize ---------------------------------- */
 
-pub(crate) fn up(x: Range<u32>) -> Range<usize> {
+pub(crate) fn up(x: Range<Offset>) -> Range<usize> {
     x.start as usize..x.end as usize
 }
 
-pub(crate) fn retr(buf: &[u8], range: Range<u32>) -> &str {
+pub(crate) fn retr(buf: &[u8], range: Range<Offset>) -> &str {
     // SAFETY: Buffer is strictly managed to be valid UTF-8 string.
     unsafe { std::str::from_utf8_unchecked(&buf[up(range)]) }
 }
 
+/// Retrieves a token from the window of two adjacent boundaries.
+fn retr_bound<'a>(buf: &'a [u8], bound: &[Offset]) -> &'a str {
+    retr(buf, bound[0]..bound[1])
+}
+
+/// Text range covered by given boundaries.
+pub(crate) fn text_range(index: &[Offset]) -> Range<Offset> {
+    index.first().copied().unwrap_or(0)..index.last().copied().unwrap_or(0)
+}
+
+/// Checks if both views are the same range of the same buffers, which implies equality.
+pub(crate) fn is_same_view<A, B>(a: &A, b: &B) -> bool
+where
+    A: RawView + ?Sized,
+    B: RawView + ?Sized,
+{
+    let ((a_text, a_index), (b_text, b_index)) = (a.inner(), b.inner());
+    std::ptr::eq(a_index, b_index) && std::ptr::eq(a_text, b_text)
+}
+
+/// FNV-1a hash of tokens, each prefixed by its length. Stable across sequence types, so that
+/// `Borrow` lookups keep working when a cached value is used.
+#[cfg(feature = "cached-hash")]
+pub(crate) fn fingerprint(buffer: &[u8], index: &[Offset]) -> u64 {
+    const PRIME: u64 = 0x100000001b3;
+
+    let mix = |hash: u64, bytes: &[u8]| {
+        bytes.iter().fold(hash, |hash, x| (hash ^ *x as u64).wrapping_mul(PRIME))
+    };
+
+    index.windows(2).fold(0xcbf29ce484222325, |hash, bound| {
+        let token = &buffer[up(bound[0]..bound[1])];
+        mix(mix(hash, &(token.len() as u64).to_le_bytes()), token)
+    })
+}
+
 /* -------------------------------------- Primary Trait ------------------------------------- */
 
-/// Viewer functionality for `StringSequence`.
-pub(crate) trait StringSequenceView {
-    fn inner(&self) -> (&[u8], &[Range<u32>]);
+mod sealed {
+    /// Raw access to the underlying buffers. Kept out of reach from downstream crates, as every
+    /// accessor assumes the buffers are valid UTF-8 and the index is in bounds.
+    ///
+    /// The index is a list of `N + 1` non-decreasing boundaries for `N` tokens, where `i`th token
+    /// spans `index[i]..index[i + 1]` of the text buffer. Therefore it is never empty.
+    pub trait RawView {
+        fn inner(&self) -> (&[u8], &[super::Offset]);
+
+        fn fmt_display(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let (buffer, index) = self.inner();
+            f.write_str(super::retr(buffer, super::text_range(index)))
+        }
+
+        fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let (buffer, index) = self.inner();
+            f.debug_list().entries(super::StringSequenceIter::new(buffer, index)).finish()
+        }
+
+        /// Value fed to hashers instead of tokens. Overridden by types which cache it.
+        #[cfg(feature = "cached-hash")]
+        fn fingerprint(&self) -> u64 {
+            let (buffer, index) = self.inner();
+            super::fingerprint(buffer, index)
+        }
+    }
+}
+
+pub(crate) use sealed::RawView;
 
-    fn iter(&self) -> StringSequenceIter {
+/// Read-only functionality shared by every string sequence type of this crate.
+///
+/// This trait is sealed; it is implemented for [`StringSequence`](crate::StringSequence),
+/// [`SharedStringSequence`](crate::SharedStringSequence) and
+/// [`MutableStringSequence`](crate::MutableStringSequence), and lets downstream code be generic
+/// over all of them.
+pub trait StringSequenceView: RawView {
+    /// Iterate over every token of the sequence.
+    fn iter(&self) -> StringSequenceIter<'_> {
         let (buffer, index) = self.inner();
-        StringSequenceIter { buffer, index: index.iter() }
+        StringSequenceIter::new(buffer, index)
     }
 
-    fn slice(&self, range: impl ToRange) -> StringSequenceIter {
+    /// Iterate over tokens in given range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    fn slice(&self, range: impl ToRange) -> StringSequenceIter<'_> {
         let (buffer, index) = self.inner();
-        StringSequenceIter { buffer, index: index[range.to_range(index.len())].iter() }
+        let range = range.to_range(index.len() - 1);
+        StringSequenceIter::new(buffer, &index[range.start..=range.end])
     }
 
+    /// Borrow the whole sequence as a [`StringSequenceRef`].
+    fn as_seq_ref(&self) -> StringSequenceRef<'_> {
+        StringSequenceRef::from_inner(self.inner())
+    }
+
+    /// Borrow given range of tokens as a [`StringSequenceRef`], without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    fn slice_ref(&self, range: impl ToRange) -> StringSequenceRef<'_> {
+        self.as_seq_ref().subsequence(range)
+    }
+
+    /// Concatenated text of all tokens, without any delimiter.
     fn text(&self) -> &str {
-        let (buffer, _) = self.inner();
-        // SAFETY: Buffer is strictly managed to be valid UTF-8 string.
-        unsafe { std::str::from_utf8_unchecked(buffer) }
+        let (buffer, index) = self.inner();
+        retr(buffer, text_range(index))
+    }
+
+    /// Number of tokens.
+    fn len(&self) -> usize {
+        self.inner().1.len() - 1
     }
 
-    fn fmt_display(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.text())
+    fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_list().entries(self.iter()).finish()
+    fn get(&self, index: usize) -> Option<&str> {
+        self.iter().nth(index)
+    }
+
+    fn first(&self) -> Option<&str> {
+        self.iter().next()
+    }
+
+    fn last(&self) -> Option<&str> {
+        self.iter().next_back()
+    }
+
+    /// Display tokens joined by given delimiter, e.g. `a/b/c` for `display_with('/')`.
+    fn display_with<D: std::fmt::Display>(&self, delim: D) -> DisplayWith<'_, D> {
+        DisplayWith::new(self.iter(), delim)
+    }
+
+    /// Checks if the sequence starts with given tokens.
+    fn starts_with(&self, other: &[impl AsRef<str>]) -> bool {
+        self.len() >= other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b.as_ref())
+    }
+
+    /// Checks if the sequence ends with given tokens.
+    fn ends_with(&self, other: &[impl AsRef<str>]) -> bool {
+        self.len() >= other.len()
+            && self.iter().rev().zip(other.iter().rev()).all(|(a, b)| a == b.as_ref())
+    }
+
+    /// Checks if given tokens appear adjacently somewhere in the sequence.
+    fn contains(&self, other: &[impl AsRef<str>]) -> bool {
+        // A failure table doesn't pay off for a few tokens, which is the common case.
+        if other.len() > 4 {
+            return self.find(other).is_some();
+        }
+
+        let mut iter = self.iter();
+
+        if other.is_empty() {
+            return true;
+        }
+
+        loop {
+            if iter.len() < other.len() {
+                break false;
+            }
+
+            if iter.clone().take(other.len()).eq(other.iter().map(|x| x.as_ref())) {
+                break true;
+            }
+
+            iter.next();
+        }
+    }
+
+    /* ----------------------------------------- Search --------------------------------------- */
+
+    /// Position of the first token where given tokens appear adjacently, in linear time.
+    fn find(&self, needle: &[impl AsRef<str>]) -> Option<usize> {
+        self.find_iter(needle).next()
+    }
+
+    /// Position of the first token of the last occurrence of given tokens, in linear time.
+    fn rfind(&self, needle: &[impl AsRef<str>]) -> Option<usize> {
+        let searcher = Searcher::new(needle, true);
+        if searcher.len() == 0 {
+            return Some(self.len());
+        }
+
+        let mut matched = 0;
+        for (index, token) in self.iter().rev().enumerate() {
+            matched = searcher.step(matched, token);
+
+            if matched == searcher.len() {
+                return Some(self.len() - 1 - index);
+            }
+        }
+
+        None
+    }
+
+    /// Positions of non-overlapping occurrences of given tokens, from the front. An empty needle
+    /// matches at every position, including [`Self::len`].
+    fn find_iter<'n, N: AsRef<str>>(&self, needle: &'n [N]) -> FindIter<'_, 'n, N> {
+        FindIter::new(self.iter(), needle)
+    }
+
+    /// Number of leading tokens which are the same in both sequences.
+    fn common_prefix_len<T: StringSequenceView + ?Sized>(&self, other: &T) -> usize {
+        self.iter().zip(other.iter()).take_while(|(a, b)| a == b).count()
+    }
+
+    /// Number of trailing tokens which are the same in both sequences.
+    fn common_suffix_len<T: StringSequenceView + ?Sized>(&self, other: &T) -> usize {
+        self.iter().rev().zip(other.iter().rev()).take_while(|(a, b)| a == b).count()
+    }
+
+    /// Copy of this sequence whose non-overlapping occurrences of `from` are replaced by `to`.
+    /// See [`str::replace`].
+    fn replace(&self, from: &[impl AsRef<str>], to: &[impl AsRef<str>]) -> MutableStringSequence {
+        let mut seq = MutableStringSequence::new();
+        let mut last = 0;
+
+        for position in self.find_iter(from) {
+            seq.extend(self.slice(last..position));
+            seq.extend(to.iter().map(|x| x.as_ref()));
+            last = position + from.len();
+        }
+
+        seq.extend(self.slice(last..));
+        seq
     }
 }
 
+impl<T: RawView + ?Sized> StringSequenceView for T {}
+
 /* ------------------------------------ Range Conversion ------------------------------------ */
 
 /// Supports conversion to valid range.
@@ -0,0 +1,540 @@
+//! Compact binary format, independent of serde.
+//!
+//! A sequence is encoded as below, where `varint` is an unsigned LEB128 integer in its shortest
+//! form, of at most 10 bytes. Every integer is endian-independent.
+//!
+//! | Field     | Encoding                                  |
+//! |-----------|-------------------------------------------|
+//! | Version   | `u8`, currently [`VERSION`]               |
+//! | Count     | `varint`, number of tokens                |
+//! | Lengths   | `varint` byte length of each token        |
+//! | Text      | UTF-8 bytes of every token, concatenated  |
+//!
+//! Decoders validate every field, including UTF-8 of each token, and reject a version they
+//! don't know. Overlong varints are rejected, so each sequence has exactly one encoding.
+//!
+//! # Aligned layout
+//!
+//! [`BinaryExt::to_aligned_bytes`] writes the in-memory layout of [`StringSequence`]
+//! instead, which [`StringSequenceRef::from_aligned_bytes`] borrows without copying. It trades
+//! size for zero-copy loading, and is readable only by builds with the same [`Offset`] width on
+//! little-endian hosts.
+//!
+//! | Field     | Encoding                                                   |
+//! |-----------|------------------------------------------------------------|
+//! | Magic     | [`ALIGNED_MAGIC`]                                          |
+//! | Version   | `u8`, currently [`VERSION`]                                |
+//! | Width     | `u8`, byte width of [`Offset`]                             |
+//! | Reserved  | two zero bytes                                             |
+//! | Count     | `u64` little-endian, number of tokens                      |
+//! | Index     | `Count + 1` little-endian [`Offset`]s, from zero to `Text` |
+//! | Text      | UTF-8 bytes of every token, concatenated                   |
+//!
+//! The index starts at byte 16, so the input should be aligned to [`Offset`].
+//!
+//! ```
+//! use strseq::binary::BinaryExt;
+//! use strseq::StringSequence;
+//!
+//! let seq = StringSequence::parse_delimited("usr/local/bin", '/');
+//! let bytes = seq.to_bytes();
+//! assert_eq!(bytes, b"\x01\x03\x03\x05\x03usrlocalbin");
+//! assert_eq!(StringSequence::from_bytes(&bytes).unwrap(), seq);
+//! ```
+
+use std::convert::Infallible;
+use std::io::{self, Read};
+
+use crate::base_trait::{to_offset, up};
+use crate::{
+    CapacityError, DecodeError, Offset, StringSequence, StringSequenceRef, StringSequenceView,
+};
+
+/// Version of the format written by this build.
+pub const VERSION: u8 = 1;
+
+/// Leading bytes of the aligned layout.
+pub const ALIGNED_MAGIC: [u8; 4] = *b"SSQA";
+
+/// Length of the header of the aligned layout, which precedes the index.
+const ALIGNED_HEADER_LEN: usize = 16;
+
+/// Upper limit of tokens to reserve before they're actually read, to not trust the count of a
+/// malformed input.
+const MAX_RESERVED_TOKENS: usize = 4096;
+
+/// Compact binary encoding of sequences, implemented for every [`StringSequenceView`].
+pub trait BinaryExt: StringSequenceView {
+    /// Encodes the sequence in the compact binary format. See [`crate::binary`].
+    fn to_bytes(&self) -> Vec<u8> {
+        collect_chunks(encoded_len(self), |emit| encode_with(self, emit))
+    }
+
+    /// Writes the same bytes as [`Self::to_bytes`].
+    fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        encode_with(self, |x| out.write_all(x))
+    }
+
+    /// Encodes the sequence in the aligned layout, which can be borrowed by
+    /// [`StringSequenceRef::from_aligned_bytes`]. See [`crate::binary`].
+    fn to_aligned_bytes(&self) -> Vec<u8> {
+        collect_chunks(aligned_len(self), |emit| encode_aligned_with(self, emit))
+    }
+
+    /// Writes the same bytes as [`Self::to_aligned_bytes`].
+    fn write_aligned_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        encode_aligned_with(self, |x| out.write_all(x))
+    }
+}
+
+impl<T: StringSequenceView + ?Sized> BinaryExt for T {}
+
+/// Collects chunks of an encoder which emits them through a callback, e.g. [`encode_with`], into
+/// a buffer of `capacity`.
+pub(crate) fn collect_chunks(
+    capacity: usize,
+    encode: impl FnOnce(&mut dyn FnMut(&[u8]) -> Result<(), Infallible>) -> Result<(), Infallible>,
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(capacity);
+    let Ok(()) = encode(&mut |x| {
+        bytes.extend_from_slice(x);
+        Ok(())
+    });
+
+    bytes
+}
+
+/// Emits encoded chunks of the sequence in order.
+pub(crate) fn encode_with<T, E>(
+    seq: &T,
+    mut emit: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: StringSequenceView + ?Sized,
+{
+    let mut buf = [0u8; 10];
+
+    emit(&[VERSION])?;
+    emit(varint(seq.len() as u64, &mut buf))?;
+    for token in seq.iter() {
+        emit(varint(token.len() as u64, &mut buf))?;
+    }
+
+    emit(seq.text().as_bytes())
+}
+
+/// Exact length of the encoding.
+pub(crate) fn encoded_len<T: StringSequenceView + ?Sized>(seq: &T) -> usize {
+    let varint_len = |x: usize| (usize::BITS - x.leading_zeros()).max(1).div_ceil(7) as usize;
+    1 + varint_len(seq.len())
+        + seq.iter().map(|x| varint_len(x.len())).sum::<usize>()
+        + seq.text().len()
+}
+
+fn varint(mut value: u64, buf: &mut [u8; 10]) -> &[u8] {
+    let mut len = 0;
+
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf[len] = byte;
+            return &buf[..=len];
+        }
+
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                          DECODING                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+enum Failure {
+    Io(io::Error),
+    Decode(DecodeError),
+}
+
+impl<T: Into<DecodeError>> From<T> for Failure {
+    fn from(value: T) -> Self {
+        Self::Decode(value.into())
+    }
+}
+
+/// Reader which tracks the position, for error reports. Varints are read byte by byte, so the
+/// reader is buffered not to issue a read for each of them.
+struct Source<R> {
+    inner: R,
+    position: usize,
+}
+
+impl<R: io::BufRead> Source<R> {
+    fn byte(&mut self) -> Result<u8, Failure> {
+        let buf = self.inner.fill_buf().map_err(Failure::Io)?;
+        let byte = *buf.first().ok_or(Failure::Io(io::ErrorKind::UnexpectedEof.into()))?;
+        self.inner.consume(1);
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, Failure> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+
+            // The 10th byte may carry only the most significant bit, and a trailing zero byte
+            // is an overlong encoding of a shorter one.
+            if (shift == 63 && byte > 1) || (shift > 0 && byte == 0) {
+                let position = self.position - 1;
+                return Err(DecodeError::InvalidByte { position, found: byte }.into());
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        unreachable!("the last byte never continues")
+    }
+
+    fn decode(&mut self) -> Result<StringSequence, Failure> {
+        let version = self.byte()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion { version }.into());
+        }
+
+        let num_tokens = usize::try_from(self.varint()?)
+            .ok()
+            .filter(|x| to_offset(*x).is_some())
+            .ok_or(CapacityError::TokenOverflow)?;
+
+        let mut index: Vec<Offset> = Vec::with_capacity(num_tokens.min(MAX_RESERVED_TOKENS) + 1);
+        index.push(0);
+
+        for _ in 0..num_tokens {
+            let start = index[index.len() - 1] as usize;
+            let end = usize::try_from(self.varint()?)
+                .ok()
+                .and_then(|x| x.checked_add(start))
+                .and_then(to_offset)
+                .ok_or(CapacityError::TextOverflow)?;
+            index.push(end);
+        }
+
+        // Grows as the text is actually read, rather than trusting the lengths.
+        let text_position = self.position;
+        let text_len = index[num_tokens] as usize;
+        let mut text = Vec::new();
+
+        self.inner.by_ref().take(text_len as u64).read_to_end(&mut text).map_err(Failure::Io)?;
+        self.position += text.len();
+
+        if text.len() != text_len {
+            return Err(Failure::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        for bound in index.windows(2) {
+            let token = &text[bound[0] as usize..bound[1] as usize];
+            if std::str::from_utf8(token).is_err() {
+                let position = text_position + bound[0] as usize;
+                return Err(DecodeError::InvalidUtf8 { position }.into());
+            }
+        }
+
+        Ok(StringSequence::from_owned_index(index, &text))
+    }
+}
+
+impl StringSequence {
+    /// Decodes a sequence written by [`BinaryExt::to_bytes`]. The input should contain
+    /// exactly one sequence.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut source = Source { inner: bytes, position: 0 };
+
+        match source.decode() {
+            Ok(_) if source.position != bytes.len() => {
+                Err(DecodeError::TrailingBytes { position: source.position })
+            }
+            Ok(seq) => Ok(seq),
+            Err(Failure::Decode(e)) => Err(e),
+            // Reading from a slice fails only at its end.
+            Err(Failure::Io(_)) => Err(DecodeError::UnexpectedEnd { position: bytes.len() }),
+        }
+    }
+
+    /// Reads a sequence written by [`BinaryExt::write_to`], leaving the rest of the
+    /// reader intact. Invalid inputs are reported as [`io::ErrorKind::InvalidData`] whose inner
+    /// error is a [`DecodeError`].
+    ///
+    /// The reader is buffered, as the header is read byte by byte; wrap an unbuffered one such as
+    /// [`std::fs::File`] into [`io::BufReader`], and keep reusing it for consecutive sequences.
+    pub fn read_from(reader: impl io::BufRead) -> io::Result<Self> {
+        match (Source { inner: reader, position: 0 }).decode() {
+            Ok(seq) => Ok(seq),
+            Err(Failure::Io(e)) => Err(e),
+            Err(Failure::Decode(e)) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                       ALIGNED LAYOUT                                       */
+/* ------------------------------------------------------------------------------------------ */
+
+const OFFSET_WIDTH: usize = std::mem::size_of::<Offset>();
+
+/// Emits chunks of the aligned layout in order.
+pub(crate) fn encode_aligned_with<T, E>(
+    seq: &T,
+    mut emit: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: StringSequenceView + ?Sized,
+{
+    let (text, index) = seq.inner();
+    let base = index[0];
+
+    emit(&ALIGNED_MAGIC)?;
+    emit(&[VERSION, OFFSET_WIDTH as u8, 0, 0])?;
+    emit(&(seq.len() as u64).to_le_bytes())?;
+    for offset in index {
+        emit(&(offset - base).to_le_bytes())?;
+    }
+
+    emit(&text[up(crate::base_trait::text_range(index))])
+}
+
+/// Exact length of the aligned layout.
+pub(crate) fn aligned_len<T: StringSequenceView + ?Sized>(seq: &T) -> usize {
+    ALIGNED_HEADER_LEN + (seq.len() + 1) * OFFSET_WIDTH + seq.text().len()
+}
+
+/// Integers which can be borrowed in place from aligned little-endian bytes.
+pub(crate) trait Word: Copy + Ord + Default {
+    /// Truncates on hosts narrower than the word, so callers check the largest one first.
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_word {
+    ($($T:ty),*) => {$(
+        impl Word for $T {
+            fn to_usize(self) -> usize {
+                self as usize
+            }
+        }
+    )*};
+}
+
+impl_word!(u16, u32, u64);
+
+/// Borrows `count` words at the start of `bytes`, which is at `position` of the input.
+pub(crate) fn borrow_words<T: Word>(
+    bytes: &[u8],
+    count: usize,
+    position: usize,
+) -> Result<&[T], DecodeError> {
+    let words = count
+        .checked_mul(std::mem::size_of::<T>())
+        .and_then(|x| bytes.get(..x))
+        .ok_or(DecodeError::UnexpectedEnd { position: position + bytes.len() })?;
+
+    if cfg!(target_endian = "big") {
+        return Err(DecodeError::UnsupportedEndian);
+    }
+
+    // Alignment is always a power of two.
+    if words.as_ptr() as usize & (std::mem::align_of::<T>() - 1) != 0 {
+        return Err(DecodeError::Misaligned { alignment: std::mem::align_of::<T>() });
+    }
+
+    // SAFETY: The range is in bounds and aligned, and every bit pattern is a valid integer, whose
+    // byte order is the same as the host's.
+    Ok(unsafe { std::slice::from_raw_parts(words.as_ptr() as *const T, count) })
+}
+
+/// Checks if boundaries start from zero and never decrease. `position` is where `index` starts
+/// in the input.
+pub(crate) fn check_boundaries<T: Word>(index: &[T], position: usize) -> Result<(), DecodeError> {
+    let at = |i: usize| position + i * std::mem::size_of::<T>();
+
+    if index.first().is_some_and(|x| *x != T::default()) {
+        return Err(DecodeError::InvalidOffset { position: at(0) });
+    }
+
+    match (1..index.len()).find(|&i| index[i] < index[i - 1]) {
+        Some(i) => Err(DecodeError::InvalidOffset { position: at(i) }),
+        None => Ok(()),
+    }
+}
+
+/// Checks if every token is valid UTF-8. `position` is where `text` starts in the input, and
+/// `index` should be already checked to be in bounds of `text`.
+pub(crate) fn check_tokens<T: Word>(
+    text: &[u8],
+    index: &[T],
+    position: usize,
+) -> Result<(), DecodeError> {
+    for bound in index.windows(2) {
+        let (start, end) = (bound[0].to_usize(), bound[1].to_usize());
+        if std::str::from_utf8(&text[start..end]).is_err() {
+            return Err(DecodeError::InvalidUtf8 { position: position + start });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the leading 8 bytes shared by aligned layouts: the magic, the version, the width of
+/// boundaries and reserved bytes. `position` is where `header` starts in the input.
+pub(crate) fn check_header(
+    header: &[u8],
+    magic: &[u8; 4],
+    width: usize,
+    position: usize,
+) -> Result<(), DecodeError> {
+    if let Some(i) = (0..magic.len()).find(|&i| header[i] != magic[i]) {
+        return Err(DecodeError::InvalidByte { position: position + i, found: header[i] });
+    }
+
+    if header[4] != VERSION {
+        return Err(DecodeError::UnsupportedVersion { version: header[4] });
+    }
+
+    if header[5] as usize != width {
+        return Err(DecodeError::OffsetWidth { expected: width as u8, found: header[5] });
+    }
+
+    match (6..8).find(|&i| header[i] != 0) {
+        Some(i) => Err(DecodeError::InvalidByte { position: position + i, found: header[i] }),
+        None => Ok(()),
+    }
+}
+
+/// Validates a sequence in the aligned layout at the start of `bytes`, and returns it with the
+/// number of bytes it occupies. Reported positions are shifted by `position`.
+pub(crate) fn parse_aligned(
+    bytes: &[u8],
+    position: usize,
+) -> Result<(StringSequenceRef<'_>, usize), DecodeError> {
+    let at = |offset: usize| position + offset;
+    let header = bytes
+        .get(..ALIGNED_HEADER_LEN)
+        .ok_or(DecodeError::UnexpectedEnd { position: at(bytes.len()) })?;
+
+    check_header(header, &ALIGNED_MAGIC, OFFSET_WIDTH, position)?;
+
+    let num_tokens = usize::try_from(u64::from_le_bytes(header[8..16].try_into().unwrap()))
+        .ok()
+        .filter(|x| to_offset(*x).is_some())
+        .ok_or(CapacityError::TokenOverflow)?;
+
+    let index_count = num_tokens.checked_add(1).ok_or(CapacityError::TokenOverflow)?;
+    let index: &[Offset] =
+        borrow_words(&bytes[ALIGNED_HEADER_LEN..], index_count, at(ALIGNED_HEADER_LEN))?;
+    check_boundaries(index, at(ALIGNED_HEADER_LEN))?;
+
+    let text_start = ALIGNED_HEADER_LEN + index.len() * OFFSET_WIDTH;
+    let text = bytes[text_start..]
+        .get(..index[num_tokens] as usize)
+        .ok_or(DecodeError::UnexpectedEnd { position: at(bytes.len()) })?;
+    check_tokens(text, index, at(text_start))?;
+
+    Ok((StringSequenceRef::from_inner((text, index)), text_start + text.len()))
+}
+
+impl<'a> StringSequenceRef<'a> {
+    /// Borrows a sequence written by [`BinaryExt::to_aligned_bytes`] without copying,
+    /// after validating it in a single pass. The input should contain exactly one sequence, and
+    /// start at an address aligned to [`Offset`].
+    ///
+    /// ```
+    /// use strseq::binary::{AlignedBytes, BinaryExt};
+    /// use strseq::{StringSequence, StringSequenceRef};
+    ///
+    /// let seq = StringSequence::parse_delimited("usr/local/bin", '/');
+    /// let bytes = AlignedBytes::from(&seq.to_aligned_bytes()[..]);
+    /// assert_eq!(StringSequenceRef::from_aligned_bytes(&bytes).unwrap(), seq);
+    /// ```
+    pub fn from_aligned_bytes(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        match parse_aligned(bytes, 0)? {
+            (seq, len) if len == bytes.len() => Ok(seq),
+            (_, len) => Err(DecodeError::TrailingBytes { position: len }),
+        }
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                        ALIGNED BUFFER                                      */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Owned bytes whose start is aligned to 8, enough to borrow any aligned layout of this crate.
+///
+/// A plain `Vec<u8>` is not guaranteed to be aligned, so inputs which are read into memory rather
+/// than memory-mapped should be loaded into this.
+#[derive(Clone, Default)]
+pub struct AlignedBytes {
+    words: Box<[u64]>,
+    len: usize,
+}
+
+impl AlignedBytes {
+    /// Reads every byte until the end of `reader`.
+    pub fn read_from(mut reader: impl io::Read) -> io::Result<Self> {
+        let mut words = Vec::<u64>::new();
+        let mut len = 0;
+
+        loop {
+            if len == words.len() * 8 {
+                words.resize((words.len() * 2).max(1024), 0);
+            }
+
+            // SAFETY: Any byte is a valid part of an integer.
+            let spare = unsafe {
+                std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8)
+            };
+
+            match reader.read(&mut spare[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        words.truncate(len.div_ceil(8));
+        Ok(Self { words: words.into_boxed_slice(), len })
+    }
+}
+
+impl From<&[u8]> for AlignedBytes {
+    fn from(value: &[u8]) -> Self {
+        let mut words = vec![0u64; value.len().div_ceil(8)].into_boxed_slice();
+
+        // SAFETY: The buffer is large enough, and any byte is a valid part of an integer.
+        unsafe {
+            let dst = words.as_mut_ptr() as *mut u8;
+            std::ptr::copy_nonoverlapping(value.as_ptr(), dst, value.len());
+        }
+
+        Self { words, len: value.len() }
+    }
+}
+
+impl std::ops::Deref for AlignedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `len` never exceeds the byte length of `words`.
+        unsafe { std::slice::from_raw_parts(self.words.as_ptr() as *const u8, self.len) }
+    }
+}
+
+impl std::fmt::Debug for AlignedBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlignedBytes").field("len", &self.len).finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,186 @@
+use std::borrow::Borrow;
+
+use crate::{
+    base_trait::{Offset, RawView, StringSequenceView, ToRange},
+    MutableStringSequence, SharedStringSequence, StringSequence,
+};
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                       UNSIZED BORROW                                       */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Borrowed form of every string sequence type, as `str` is for `String`.
+///
+/// Every sequence type dereferences to `StrSeq`, and implements `Borrow<StrSeq>` with `Hash`,
+/// `Eq` and `Ord` consistent with the owner. Therefore a map keyed by any of sequence types can be
+/// queried by any other sequence, including a borrowed [`StringSequenceRef`], without allocation.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use strseq::{SharedStringSequence, StringSequence, StringSequenceView};
+///
+/// let mut map = HashMap::new();
+/// map.insert(SharedStringSequence::from_slice(&["a", "b"]), 1);
+///
+/// let key = StringSequence::from_slice(&["a", "b", "c"]);
+/// assert_eq!(map.get(&*key.slice_ref(..2)), Some(&1));
+/// ```
+#[repr(transparent)]
+pub struct StrSeq(dyn RawView);
+
+impl_seq_view!(StrSeq);
+
+impl StrSeq {
+    pub(crate) fn new<T: RawView + 'static>(view: &T) -> &Self {
+        let view: &dyn RawView = view;
+
+        // SAFETY: `StrSeq` is a transparent wrapper of `dyn RawView`
+        unsafe { &*(view as *const dyn RawView as *const Self) }
+    }
+}
+
+impl RawView for StrSeq {
+    fn inner(&self) -> (&[u8], &[Offset]) {
+        self.0.inner()
+    }
+
+    #[cfg(feature = "cached-hash")]
+    fn fingerprint(&self) -> u64 {
+        self.0.fingerprint()
+    }
+}
+
+impl ToOwned for StrSeq {
+    type Owned = StringSequence;
+
+    fn to_owned(&self) -> Self::Owned {
+        StringSequence::from(self)
+    }
+}
+
+macro_rules! impl_borrow {
+    ($Type:ident) => {
+        impl std::ops::Deref for $Type {
+            type Target = StrSeq;
+
+            fn deref(&self) -> &Self::Target {
+                StrSeq::new(self)
+            }
+        }
+
+        impl Borrow<StrSeq> for $Type {
+            fn borrow(&self) -> &StrSeq {
+                self
+            }
+        }
+    };
+}
+
+impl_borrow!(StringSequence);
+impl_borrow!(SharedStringSequence);
+impl_borrow!(MutableStringSequence);
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                        BORROWED VIEW                                       */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Borrowed view of a range of tokens of any sequence. Cheap to copy.
+#[derive(Clone, Copy)]
+pub struct StringSequenceRef<'a> {
+    text: &'a [u8],
+    index: &'a [Offset],
+}
+
+impl_seq_view!(StringSequenceRef<'a>);
+
+impl<'a> StringSequenceRef<'a> {
+    pub(crate) fn from_inner((text, index): (&'a [u8], &'a [Offset])) -> Self {
+        Self { text, index }
+    }
+
+    /// Narrows this view into given range of tokens.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn subsequence(&self, range: impl ToRange) -> Self {
+        let range = range.to_range(self.index.len() - 1);
+        Self { text: self.text, index: &self.index[range.start..=range.end] }
+    }
+
+    /// Longest common prefix of given sequences of any type, borrowed from the first one. Returns
+    /// `None` if there's no sequence.
+    pub fn common_prefix<T>(seqs: impl IntoIterator<Item = &'a T>) -> Option<Self>
+    where
+        T: StringSequenceView + ?Sized + 'a,
+    {
+        let mut seqs = seqs.into_iter();
+        let first = seqs.next()?.as_seq_ref();
+        let len = seqs.fold(first.len(), |len, x| len.min(first.common_prefix_len(x)));
+        Some(first.subsequence(..len))
+    }
+
+    /// Longest common suffix of given sequences of any type, borrowed from the first one. Returns
+    /// `None` if there's no sequence.
+    pub fn common_suffix<T>(seqs: impl IntoIterator<Item = &'a T>) -> Option<Self>
+    where
+        T: StringSequenceView + ?Sized + 'a,
+    {
+        let mut seqs = seqs.into_iter();
+        let first = seqs.next()?.as_seq_ref();
+        let len = seqs.fold(first.len(), |len, x| len.min(first.common_suffix_len(x)));
+        Some(first.subsequence(first.len() - len..))
+    }
+}
+
+impl RawView for StringSequenceRef<'_> {
+    fn inner(&self) -> (&[u8], &[Offset]) {
+        (self.text, self.index)
+    }
+}
+
+impl std::ops::Deref for StringSequenceRef<'_> {
+    type Target = StrSeq;
+
+    fn deref(&self) -> &Self::Target {
+        let view: &dyn RawView = self;
+
+        // SAFETY: `StrSeq` is a transparent wrapper of `dyn RawView`. Erasing the lifetime bound of
+        // the trait object is fine, as `RawView` can't hand out anything that outlives the borrow
+        // of `self`.
+        unsafe { std::mem::transmute::<&dyn RawView, &StrSeq>(view) }
+    }
+}
+
+impl Borrow<StrSeq> for StringSequenceRef<'_> {
+    fn borrow(&self) -> &StrSeq {
+        self
+    }
+}
+
+impl<'a> IntoIterator for StringSequenceRef<'a> {
+    type Item = &'a str;
+    type IntoIter = crate::StringSequenceIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        crate::StringSequenceIter::new(self.text, self.index)
+    }
+}
+
+impl<'a> From<StringSequenceRef<'a>> for StringSequence {
+    fn from(value: StringSequenceRef<'a>) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl<'a> From<StringSequenceRef<'a>> for SharedStringSequence {
+    fn from(value: StringSequenceRef<'a>) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl<'a> From<StringSequenceRef<'a>> for MutableStringSequence {
+    fn from(value: StringSequenceRef<'a>) -> Self {
+        Self::from(&value)
+    }
+}
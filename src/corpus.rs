@@ -0,0 +1,376 @@
+//! Read-only collection of many sequences in a single buffer, which is borrowed without copying.
+//!
+//! Tokens of every sequence share a single text and boundary array, and a table maps each
+//! sequence to its range of tokens. Every integer is little-endian, and the input should be
+//! aligned to 8, e.g. memory-mapped or loaded into [`AlignedBytes`](crate::binary::AlignedBytes).
+//!
+//! | Field     | Encoding                                                     |
+//! |-----------|--------------------------------------------------------------|
+//! | Magic     | [`MAGIC`]                                                    |
+//! | Version   | `u8`, currently [`VERSION`]                                  |
+//! | Width     | `u8`, byte width of boundaries, always 8                     |
+//! | Reserved  | two zero bytes                                               |
+//! | Count     | `u64`, number of sequences                                   |
+//! | Tokens    | `u64`, number of tokens of all sequences                     |
+//! | Table     | `Count + 1` `u64`s, first token of each sequence, to `Tokens`|
+//! | Index     | `Tokens + 1` `u64`s, from zero to `Text`                     |
+//! | Text      | UTF-8 bytes of every token, concatenated                     |
+//!
+//! Opening a corpus validates the whole input once, then every access is a plain lookup.
+//! Boundaries are `u64` regardless of [`Offset`](crate::Offset), so a corpus is not limited by
+//! it, and is readable by every build. Sequences are handed out as [`Entry`], which borrows them
+//! in place.
+//!
+//! ```
+//! use strseq::{binary::AlignedBytes, corpus::{Corpus, CorpusWriter}, StringSequence};
+//!
+//! let path = |x: &str| StringSequence::parse_delimited(x, '/');
+//! let mut writer = CorpusWriter::new();
+//! for x in ["etc/hosts", "usr/bin", "usr/local/bin"] {
+//!     writer.push(&path(x));
+//! }
+//!
+//! let bytes = AlignedBytes::from(&writer.to_bytes()[..]);
+//! let corpus = Corpus::new(&bytes).unwrap();
+//! assert_eq!(corpus.get(1).unwrap().text(), "usrbin");
+//! assert_eq!(corpus.get(2).unwrap(), path("usr/local/bin"));
+//! assert_eq!(corpus.binary_search(&path("usr/local/bin")), Ok(2));
+//! assert_eq!(corpus.binary_search(&path("usr/lib")), Err(2));
+//! ```
+
+use std::{cmp::Ordering, io, ops::Range};
+
+use crate::{
+    binary::{borrow_words, check_boundaries, check_header, check_tokens, collect_chunks, VERSION},
+    DecodeError, StringSequence, StringSequenceView,
+};
+
+/// Leading bytes of a corpus.
+pub const MAGIC: [u8; 4] = *b"SSQC";
+
+/// Length of the header, which precedes the table.
+const HEADER_LEN: usize = 24;
+
+/// Byte width of every integer after the header.
+const WIDTH: usize = 8;
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           WRITER                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Builds a corpus in memory, to be written by [`Self::write_to`].
+#[derive(Debug, Clone)]
+pub struct CorpusWriter {
+    /// Text of every token, concatenated.
+    text: String,
+    /// Boundaries of every token in `text`.
+    index: Vec<u64>,
+    /// First token of each sequence, followed by the number of tokens.
+    table: Vec<u64>,
+}
+
+impl Default for CorpusWriter {
+    fn default() -> Self {
+        Self { text: String::new(), index: vec![0], table: vec![0] }
+    }
+}
+
+impl CorpusWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of sequences pushed so far.
+    pub fn len(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends a sequence, and returns its ordinal. Push sequences in order to allow
+    /// [`Corpus::binary_search`].
+    pub fn push<T: StringSequenceView + ?Sized>(&mut self, seq: &T) -> usize {
+        for token in seq.iter() {
+            self.text.push_str(token);
+            self.index.push(self.text.len() as u64);
+        }
+
+        self.table.push(self.index.len() as u64 - 1);
+        self.len() - 1
+    }
+
+    /// Encodes the corpus into bytes, which can be opened by [`Corpus::new`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        collect_chunks(self.encoded_len(), |emit| self.encode_with(emit))
+    }
+
+    /// Writes the same bytes as [`Self::to_bytes`].
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        self.encode_with(|x| out.write_all(x))
+    }
+
+    fn encoded_len(&self) -> usize {
+        HEADER_LEN + (self.table.len() + self.index.len()) * WIDTH + self.text.len()
+    }
+
+    fn encode_with<E>(&self, mut emit: impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        emit(&MAGIC)?;
+        emit(&[VERSION, WIDTH as u8, 0, 0])?;
+        emit(&(self.len() as u64).to_le_bytes())?;
+        emit(&(self.index.len() as u64 - 1).to_le_bytes())?;
+        for word in self.table.iter().chain(&self.index) {
+            emit(&word.to_le_bytes())?;
+        }
+
+        emit(self.text.as_bytes())
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           READER                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Validated view of a corpus written by [`CorpusWriter`]. Cheap to copy.
+#[derive(Clone, Copy)]
+pub struct Corpus<'a> {
+    table: &'a [u64],
+    index: &'a [u64],
+    text: &'a str,
+}
+
+impl<'a> Corpus<'a> {
+    /// Validates the corpus in `bytes`, which should start at an address aligned to 8 and contain
+    /// exactly one corpus.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let header =
+            bytes.get(..HEADER_LEN).ok_or(DecodeError::UnexpectedEnd { position: bytes.len() })?;
+
+        check_header(header, &MAGIC, WIDTH, 0)?;
+
+        // Counts which can't even be represented don't fit into the input either.
+        let count = |range: Range<usize>| {
+            usize::try_from(u64::from_le_bytes(header[range].try_into().unwrap()))
+                .ok()
+                .and_then(|x| x.checked_add(1))
+                .ok_or(DecodeError::UnexpectedEnd { position: bytes.len() })
+        };
+
+        let table_count = count(8..16)?;
+        let index_count = count(16..24)?;
+
+        let table: &[u64] = borrow_words(&bytes[HEADER_LEN..], table_count, HEADER_LEN)?;
+        check_boundaries(table, HEADER_LEN)?;
+
+        if table[table_count - 1] != index_count as u64 - 1 {
+            let position = HEADER_LEN + (table_count - 1) * WIDTH;
+            return Err(DecodeError::InvalidOffset { position });
+        }
+
+        let index_start = HEADER_LEN + table.len() * WIDTH;
+        let index: &[u64] = borrow_words(&bytes[index_start..], index_count, index_start)?;
+        check_boundaries(index, index_start)?;
+
+        // Every boundary is in bounds of the text once the last one is.
+        let text_start = index_start + index.len() * WIDTH;
+        let text = usize::try_from(index[index_count - 1])
+            .ok()
+            .and_then(|len| text_start.checked_add(len))
+            .and_then(|end| bytes.get(text_start..end))
+            .ok_or(DecodeError::UnexpectedEnd { position: bytes.len() })?;
+        let text_end = text_start + text.len();
+        check_tokens(text, index, text_start)?;
+
+        if text_end != bytes.len() {
+            return Err(DecodeError::TrailingBytes { position: text_end });
+        }
+
+        // SAFETY: Tokens cover the whole text, and each of them is valid UTF-8.
+        let text = unsafe { std::str::from_utf8_unchecked(text) };
+        Ok(Self { table, index, text })
+    }
+
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sequence of given ordinal.
+    pub fn get(&self, ordinal: usize) -> Option<Entry<'a>> {
+        let (start, end) = (*self.table.get(ordinal)?, *self.table.get(ordinal + 1)?);
+        Some(Entry { index: &self.index[start as usize..=end as usize], text: self.text })
+    }
+
+    pub fn iter(&self) -> Iter<'a> {
+        Iter { corpus: *self, range: 0..self.len() }
+    }
+
+    /// Binary searches a sequence equal to `seq`, as [`slice::binary_search`] does. Sequences
+    /// should've been pushed in order, otherwise the result is meaningless.
+    pub fn binary_search<T: StringSequenceView + ?Sized>(&self, seq: &T) -> Result<usize, usize> {
+        self.binary_search_by(|x| x.iter().cmp(seq.iter()))
+    }
+
+    /// Binary searches with a comparator, as [`slice::binary_search_by`] does.
+    pub fn binary_search_by(
+        &self,
+        mut compare: impl FnMut(Entry<'a>) -> Ordering,
+    ) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match compare(self.get(mid).unwrap()) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+
+        Err(low)
+    }
+}
+
+impl std::fmt::Debug for Corpus<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for Corpus<'a> {
+    type Item = Entry<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over sequences of a [`Corpus`], in order.
+#[derive(Clone)]
+pub struct Iter<'a> {
+    corpus: Corpus<'a>,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().and_then(|x| self.corpus.get(x))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().and_then(|x| self.corpus.get(x))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                            ENTRY                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Sequence of a [`Corpus`], borrowed in place.
+///
+/// Its boundaries are `u64`s rather than [`Offset`](crate::Offset)s, so it's not a
+/// [`StringSequenceView`]. It compares equal to any view of the same tokens, and converts into a
+/// [`StringSequence`] for the rest of the API.
+#[derive(Clone, Copy)]
+pub struct Entry<'a> {
+    /// Boundaries of tokens in `text`, one more than the number of tokens.
+    index: &'a [u64],
+    /// Text of the whole corpus.
+    text: &'a str,
+}
+
+impl<'a> Entry<'a> {
+    /// Number of tokens.
+    pub fn len(&self) -> usize {
+        self.index.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Token of given index.
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        let (start, end) = (*self.index.get(index)?, *self.index.get(index + 1)?);
+        Some(&self.text[start as usize..end as usize])
+    }
+
+    /// Text of every token, concatenated.
+    pub fn text(&self) -> &'a str {
+        &self.text[self.index[0] as usize..self.index[self.len()] as usize]
+    }
+
+    pub fn iter(&self) -> Tokens<'a> {
+        Tokens { entry: *self, range: 0..self.len() }
+    }
+}
+
+impl<T: StringSequenceView + ?Sized> PartialEq<T> for Entry<'_> {
+    fn eq(&self, other: &T) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl std::fmt::Debug for Entry<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl From<Entry<'_>> for StringSequence {
+    fn from(value: Entry<'_>) -> Self {
+        value.iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for Entry<'a> {
+    type Item = &'a str;
+    type IntoIter = Tokens<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over tokens of an [`Entry`], in order.
+#[derive(Clone)]
+pub struct Tokens<'a> {
+    entry: Entry<'a>,
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().and_then(|x| self.entry.get(x))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Tokens<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().and_then(|x| self.entry.get(x))
+    }
+}
+
+impl ExactSizeIterator for Tokens<'_> {}
@@ -0,0 +1,395 @@
+//! Parsing from and formatting into delimited strings, e.g. `a/b/c`.
+
+use std::fmt;
+
+use crate::{
+    base_trait::{to_offset, Offset},
+    CapacityError, DelimitedError, MutableStringSequence, SharedStringSequence, StringSequence,
+    StringSequenceIter, StringSequenceRef, StringSequenceView,
+};
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           PARSING                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+impl StringSequence {
+    /// Splits `text` by `delim`, and builds the compact representation directly from it. Unlike
+    /// collecting from [`str::split`], the buffer is allocated only once.
+    ///
+    /// Follows the semantics of [`str::split`]; empty text results in a single empty token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length or the number of tokens overflows [`Offset`].
+    pub fn parse_delimited(text: &str, delim: char) -> Self {
+        Self::try_parse_delimited(text, delim).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::parse_delimited`].
+    pub fn try_parse_delimited(text: &str, delim: char) -> Result<Self, CapacityError> {
+        let num_tokens = text.matches(delim).count() + 1;
+        let text_len = text.len() - (num_tokens - 1) * delim.len_utf8();
+        to_offset(num_tokens).ok_or(CapacityError::TokenOverflow)?;
+        to_offset(text_len).ok_or(CapacityError::TextOverflow)?;
+
+        // SAFETY: Boundaries are accumulated from lengths of split tokens, which are all valid
+        // UTF-8 strings.
+        let this = unsafe {
+            Self::from_layout(num_tokens, text_len, |index, buffer| {
+                let mut offset = 0;
+                for (bound, token) in index[1..].iter_mut().zip(text.split(delim)) {
+                    buffer[offset..offset + token.len()].copy_from_slice(token.as_bytes());
+                    offset += token.len();
+                    *bound = offset as Offset;
+                }
+            })
+        };
+
+        Ok(this)
+    }
+}
+
+impl SharedStringSequence {
+    /// See [`StringSequence::parse_delimited`].
+    pub fn parse_delimited(text: &str, delim: char) -> Self {
+        StringSequence::parse_delimited(text, delim).into()
+    }
+
+    /// See [`StringSequence::try_parse_delimited`].
+    pub fn try_parse_delimited(text: &str, delim: char) -> Result<Self, CapacityError> {
+        StringSequence::try_parse_delimited(text, delim).map(Into::into)
+    }
+}
+
+impl MutableStringSequence {
+    /// See [`StringSequence::parse_delimited`].
+    pub fn parse_delimited(text: &str, delim: char) -> Self {
+        Self::try_parse_delimited(text, delim).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// See [`StringSequence::try_parse_delimited`].
+    pub fn try_parse_delimited(text: &str, delim: char) -> Result<Self, CapacityError> {
+        let mut this = Self::with_capacity(0, text.len());
+        this.try_extend(text.split(delim))?;
+        Ok(this)
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                         FORMATTING                                         */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Displays tokens of a sequence joined by a delimiter, without intermediate allocation.
+///
+/// Created by [`StringSequenceView::display_with`](crate::StringSequenceView::display_with).
+#[derive(Debug, Clone)]
+pub struct DisplayWith<'a, D> {
+    iter: StringSequenceIter<'a>,
+    delim: D,
+}
+
+impl<'a, D> DisplayWith<'a, D> {
+    pub(crate) fn new(iter: StringSequenceIter<'a>, delim: D) -> Self {
+        Self { iter, delim }
+    }
+}
+
+impl<D: fmt::Display> fmt::Display for DisplayWith<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, token) in self.iter.clone().enumerate() {
+            if index > 0 {
+                self.delim.fmt(f)?;
+            }
+
+            f.write_str(token)?;
+        }
+
+        Ok(())
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                            CODEC                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Lossless conversion between sequences and delimited strings, for tokens which may contain the
+/// delimiter itself.
+///
+/// - Tokens are joined by the delimiter.
+/// - Delimiter, escape and quote characters inside a token are prefixed by the escape character.
+/// - If quoting is enabled, tokens containing the delimiter are wrapped in quotes instead, where
+///   only quote and escape characters are escaped.
+/// - Empty text stands for a single empty token, as [`str::split`] does. Therefore an empty
+///   sequence is written as a lone escape character.
+///
+/// ```
+/// use strseq::{delimited::DelimitedCodec, StringSequence};
+///
+/// let seq = StringSequence::from_slice(&["a/b", "c\\", ""]);
+///
+/// let codec = DelimitedCodec::new('/', '\\');
+/// assert_eq!(codec.encode(&seq), r"a\/b/c\\/");
+/// assert_eq!(StringSequence::parse_with(r"a\/b/c\\/", &codec).unwrap(), seq);
+///
+/// let codec = codec.with_quote('"');
+/// assert_eq!(codec.display(&seq).to_string(), r#""a/b"/c\\/"#);
+/// assert_eq!(StringSequence::parse_with(r#""a/b"/c\\/"#, &codec).unwrap(), seq);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelimitedCodec {
+    delimiter: char,
+    escape: char,
+    quote: Option<char>,
+}
+
+impl Default for DelimitedCodec {
+    /// Delimited by `/` and escaped by `\`, without quoting.
+    fn default() -> Self {
+        Self::new('/', '\\')
+    }
+}
+
+impl DelimitedCodec {
+    /// # Panics
+    ///
+    /// Panics if `delimiter` and `escape` are the same.
+    pub const fn new(delimiter: char, escape: char) -> Self {
+        assert!(delimiter != escape, "delimiter and escape must differ");
+        Self { delimiter, escape, quote: None }
+    }
+
+    /// Enables quoting of tokens which contain the delimiter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quote` is the same as the delimiter or the escape character.
+    pub const fn with_quote(self, quote: char) -> Self {
+        assert!(quote != self.delimiter && quote != self.escape, "quote must differ from others");
+        Self { quote: Some(quote), ..self }
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn escape(&self) -> char {
+        self.escape
+    }
+
+    pub fn quote(&self) -> Option<char> {
+        self.quote
+    }
+
+    /* ---------------------------------------- Encode ---------------------------------------- */
+
+    /// Writes encoded sequence into `out`.
+    pub fn encode_to<T, W>(&self, seq: &T, out: &mut W) -> fmt::Result
+    where
+        T: StringSequenceView + ?Sized,
+        W: fmt::Write + ?Sized,
+    {
+        if seq.is_empty() {
+            return out.write_char(self.escape);
+        }
+
+        for (index, token) in seq.iter().enumerate() {
+            if index > 0 {
+                out.write_char(self.delimiter)?;
+            }
+
+            match self.quote {
+                Some(quote) if token.contains(self.delimiter) => {
+                    out.write_char(quote)?;
+                    self.write_escaped(token, |c| c == quote || c == self.escape, out)?;
+                    out.write_char(quote)?;
+                }
+                _ => self.write_escaped(token, |c| self.is_special(c), out)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_escaped<W: fmt::Write + ?Sized>(
+        &self,
+        token: &str,
+        needs_escape: impl Fn(char) -> bool,
+        out: &mut W,
+    ) -> fmt::Result {
+        for c in token.chars() {
+            if needs_escape(c) {
+                out.write_char(self.escape)?;
+            }
+
+            out.write_char(c)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_special(&self, c: char) -> bool {
+        c == self.delimiter || c == self.escape || Some(c) == self.quote
+    }
+
+    /// Encodes the sequence into a new string.
+    pub fn encode<T: StringSequenceView + ?Sized>(&self, seq: &T) -> String {
+        self.display(seq).to_string()
+    }
+
+    /// Displays encoded sequence, without intermediate allocation.
+    pub fn display<'a, T: StringSequenceView + ?Sized>(&self, seq: &'a T) -> Encoded<'a> {
+        Encoded { codec: *self, seq: seq.as_seq_ref() }
+    }
+
+    /* ---------------------------------------- Decode ---------------------------------------- */
+
+    /// Decodes `text` into a new sequence.
+    pub fn decode(&self, text: &str) -> Result<MutableStringSequence, DelimitedError> {
+        let mut seq = MutableStringSequence::with_capacity(0, text.len());
+        self.decode_into(text, &mut seq)?;
+        Ok(seq)
+    }
+
+    /// Appends decoded tokens of `text` into `out`. Nothing is appended on failure.
+    pub fn decode_into(
+        &self,
+        text: &str,
+        out: &mut MutableStringSequence,
+    ) -> Result<(), DelimitedError> {
+        let prev_len = out.len();
+        let result = self.decode_with(text, |token| Ok(out.try_push_back(token)?));
+
+        if result.is_err() {
+            out.drain(prev_len..);
+        }
+
+        result
+    }
+
+    fn decode_with(
+        &self,
+        text: &str,
+        mut push: impl FnMut(&str) -> Result<(), DelimitedError>,
+    ) -> Result<(), DelimitedError> {
+        if text.len() == self.escape.len_utf8() && text.starts_with(self.escape) {
+            return Ok(()); // An empty sequence
+        }
+
+        let mut chars = text.char_indices();
+        let mut token = String::new();
+
+        loop {
+            token.clear();
+
+            let has_next = match chars.clone().next() {
+                Some((position, c)) if Some(c) == self.quote => {
+                    chars.next();
+                    self.decode_quoted(&mut chars, position, &mut token)?
+                }
+                _ => self.decode_unquoted(&mut chars, &mut token)?,
+            };
+
+            push(&token)?;
+
+            if !has_next {
+                break Ok(());
+            }
+        }
+    }
+
+    /// Decodes a token until the next delimiter. Returns whether another token follows.
+    fn decode_unquoted(
+        &self,
+        chars: &mut std::str::CharIndices,
+        token: &mut String,
+    ) -> Result<bool, DelimitedError> {
+        while let Some((position, c)) = chars.next() {
+            if c == self.delimiter {
+                return Ok(true);
+            } else if c == self.escape {
+                match chars.next() {
+                    Some((_, c)) if self.is_special(c) => token.push(c),
+                    Some((_, found)) => {
+                        return Err(DelimitedError::InvalidEscape { position, found })
+                    }
+                    None => return Err(DelimitedError::DanglingEscape { position }),
+                }
+            } else if Some(c) == self.quote {
+                return Err(DelimitedError::UnexpectedQuote { position });
+            } else {
+                token.push(c);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Decodes a quoted token, whose opening quote at `open` is already consumed. Returns whether
+    /// another token follows.
+    fn decode_quoted(
+        &self,
+        chars: &mut std::str::CharIndices,
+        open: usize,
+        token: &mut String,
+    ) -> Result<bool, DelimitedError> {
+        while let Some((position, c)) = chars.next() {
+            if c == self.escape {
+                match chars.next() {
+                    Some((_, c)) if c == self.escape || Some(c) == self.quote => token.push(c),
+                    Some((_, found)) => {
+                        return Err(DelimitedError::InvalidEscape { position, found })
+                    }
+                    None => return Err(DelimitedError::DanglingEscape { position }),
+                }
+            } else if Some(c) == self.quote {
+                return match chars.next() {
+                    Some((_, c)) if c == self.delimiter => Ok(true),
+                    Some(_) => Err(DelimitedError::UnexpectedQuote { position }),
+                    None => Ok(false),
+                };
+            } else {
+                token.push(c);
+            }
+        }
+
+        Err(DelimitedError::UnterminatedQuote { position: open })
+    }
+}
+
+/// Displays a sequence encoded by [`DelimitedCodec`].
+///
+/// Created by [`DelimitedCodec::display`].
+#[derive(Debug, Clone, Copy)]
+pub struct Encoded<'a> {
+    codec: DelimitedCodec,
+    seq: StringSequenceRef<'a>,
+}
+
+impl fmt::Display for Encoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.codec.encode_to(&self.seq, f)
+    }
+}
+
+/* ------------------------------------ Codec Constructors ----------------------------------- */
+
+impl StringSequence {
+    /// Decodes `text` encoded by given codec.
+    pub fn parse_with(text: &str, codec: &DelimitedCodec) -> Result<Self, DelimitedError> {
+        codec.decode(text).map(Into::into)
+    }
+}
+
+impl SharedStringSequence {
+    /// Decodes `text` encoded by given codec.
+    pub fn parse_with(text: &str, codec: &DelimitedCodec) -> Result<Self, DelimitedError> {
+        codec.decode(text).map(Into::into)
+    }
+}
+
+impl MutableStringSequence {
+    /// Decodes `text` encoded by given codec.
+    pub fn parse_with(text: &str, codec: &DelimitedCodec) -> Result<Self, DelimitedError> {
+        codec.decode(text)
+    }
+}
@@ -0,0 +1,246 @@
+//! Token-level difference between sequences.
+
+use std::ops::Range;
+
+use crate::{MutableStringSequence, PatchError, StringSequenceView};
+
+/// Kind of an [`Edit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditKind {
+    /// Tokens are the same in both sequences.
+    Keep,
+    /// Tokens of the new sequence are inserted; `old` is empty.
+    Insert,
+    /// Tokens of the old sequence are deleted; `new` is empty.
+    Delete,
+    /// Tokens of the old sequence are replaced by ones of the new sequence.
+    Replace,
+}
+
+/// A step of an edit script, which maps `old` tokens of the old sequence to `new` tokens of the
+/// new sequence. Steps of a script returned by [`diff`] cover both sequences in order, without
+/// gaps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edit {
+    pub kind: EditKind,
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Computes the shortest edit script which turns `old` into `new`, with Myers' algorithm in
+/// `O((N + M) * D)` time and `O(N + M + D²)` memory, where `D` is the number of inserted and
+/// deleted tokens.
+///
+/// Adjacent deletions and insertions are merged into a [`EditKind::Replace`].
+///
+/// ```
+/// use strseq::{diff::{diff, EditKind}, StringSequence};
+///
+/// let old = StringSequence::parse_delimited("app/db/host", '/');
+/// let new = StringSequence::parse_delimited("app/database/host/port", '/');
+///
+/// let kinds: Vec<_> = diff(&old, &new).into_iter().map(|x| x.kind).collect();
+/// assert_eq!(kinds, [EditKind::Keep, EditKind::Replace, EditKind::Keep, EditKind::Insert]);
+/// ```
+pub fn diff<A, B>(old: &A, new: &B) -> Vec<Edit>
+where
+    A: StringSequenceView + ?Sized,
+    B: StringSequenceView + ?Sized,
+{
+    let old: Vec<_> = old.iter().collect();
+    let new: Vec<_> = new.iter().collect();
+
+    let mut script = Vec::<Edit>::new();
+    let (mut i, mut j) = (0, 0);
+
+    for step in shortest_path(&old, &new) {
+        let (kind, di, dj) = match step {
+            Step::Keep => (EditKind::Keep, 1, 1),
+            Step::Delete => (EditKind::Delete, 1, 0),
+            Step::Insert => (EditKind::Insert, 0, 1),
+        };
+
+        // Adjacent changes are merged, regardless of their kinds.
+        let is_change = |x: EditKind| x != EditKind::Keep;
+        match script.last_mut() {
+            Some(last) if last.kind == kind || (is_change(last.kind) && is_change(kind)) => {
+                last.old.end += di;
+                last.new.end += dj;
+
+                if is_change(kind) && !last.old.is_empty() && !last.new.is_empty() {
+                    last.kind = EditKind::Replace;
+                }
+            }
+            _ => script.push(Edit { kind, old: i..i + di, new: j..j + dj }),
+        }
+
+        i += di;
+        j += dj;
+    }
+
+    script
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Myers' greedy algorithm, which records the furthest reaching path of every diagonal for each
+/// number of edits, then backtracks from the end. Backtracking through round `d` reads only
+/// diagonals within `-d..=d` of the previous round, so only that window is recorded.
+fn shortest_path(old: &[&str], new: &[&str]) -> Vec<Step> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    let at = |k: isize| (k + offset) as usize;
+
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+
+    'search: for d in 0..=n + m {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            v[at(k)] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut steps = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, window) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let v = |k: isize| window[(k + d) as usize];
+
+        // Round zero only follows the diagonal from the origin.
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let k = x - y;
+            let prev_k = if k == -d || (k != d && v(k - 1) < v(k + 1)) { k + 1 } else { k - 1 };
+            (v(prev_k), v(prev_k) - prev_k)
+        };
+
+        while x > prev_x && y > prev_y {
+            steps.push(Step::Keep);
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            steps.push(if x == prev_x { Step::Insert } else { Step::Delete });
+        }
+
+        (x, y) = (prev_x, prev_y);
+    }
+
+    steps.reverse();
+    steps
+}
+
+/// Minimum number of token insertions, deletions and substitutions to turn `a` into `b`.
+pub fn levenshtein<A, B>(a: &A, b: &B) -> usize
+where
+    A: StringSequenceView + ?Sized,
+    B: StringSequenceView + ?Sized,
+{
+    let b: Vec<_> = b.iter().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, y) in b.iter().enumerate() {
+            let substitute = diagonal + (x != *y) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                            PATCH                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+impl MutableStringSequence {
+    /// Applies an edit script from [`diff`], taking inserted and replacing tokens from `new`.
+    ///
+    /// Kept tokens are taken from `self`, so a script can be applied to a sequence other than the
+    /// original one, as long as its length is compatible. Tokens after the last step are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics on the same conditions as [`Self::try_apply_patch`].
+    pub fn apply_patch<T: StringSequenceView + ?Sized>(&mut self, script: &[Edit], new: &T) {
+        self.try_apply_patch(script, new).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::apply_patch`]. Fails if `old` ranges of the script are not
+    /// contiguous from zero or exceed this sequence, if `new` ranges exceed `new`, if ranges of a
+    /// step don't fit its kind, or if the result overflows. The sequence is not modified on
+    /// failure.
+    pub fn try_apply_patch<T: StringSequenceView + ?Sized>(
+        &mut self,
+        script: &[Edit],
+        new: &T,
+    ) -> Result<(), PatchError> {
+        let mut patched = MutableStringSequence::new();
+        let mut cursor = 0;
+
+        for (step, edit) in script.iter().enumerate() {
+            if edit.old.start != cursor
+                || edit.old.start > edit.old.end
+                || edit.old.end > self.len()
+            {
+                return Err(PatchError::OldRange { step });
+            } else if edit.new.start > edit.new.end || edit.new.end > new.len() {
+                return Err(PatchError::NewRange { step });
+            }
+
+            let fits = match edit.kind {
+                EditKind::Keep => edit.old.len() == edit.new.len(),
+                EditKind::Insert => edit.old.is_empty(),
+                EditKind::Delete => edit.new.is_empty(),
+                EditKind::Replace => true,
+            };
+            if !fits {
+                return Err(PatchError::KindMismatch { step });
+            }
+
+            match edit.kind {
+                EditKind::Keep => patched.try_extend(self.slice(edit.old.clone()))?,
+                EditKind::Delete => (),
+                EditKind::Insert | EditKind::Replace => {
+                    patched.try_extend(new.slice(edit.new.clone()))?
+                }
+            }
+
+            cursor = edit.old.end;
+        }
+
+        patched.try_extend(self.slice(cursor..))?;
+        *self = patched;
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt;
+
+/// Error returned when a sequence would outgrow [`Offset`](crate::Offset).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapacityError {
+    /// Text length would overflow [`Offset`](crate::Offset).
+    TextOverflow,
+    /// Number of tokens would overflow [`Offset`](crate::Offset).
+    TokenOverflow,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TextOverflow => f.write_str("text length overflows offset"),
+            Self::TokenOverflow => f.write_str("token count overflows offset"),
+        }
+    }
+}
+
+impl std::error::Error for CapacityError {}
+
+/// Error returned by fallible mutations of [`MutableStringSequence`](crate::MutableStringSequence)
+/// which address a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutationError {
+    /// Tried to remove a token from an empty sequence.
+    Empty,
+    /// Token index is out of bounds.
+    OutOfBounds { index: usize, len: usize },
+    /// Inserted token doesn't fit into [`Offset`](crate::Offset).
+    Capacity(CapacityError),
+}
+
+impl fmt::Display for MutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("sequence is empty"),
+            Self::OutOfBounds { index, len } => {
+                write!(f, "index {index} is out of bounds for sequence of length {len}")
+            }
+            Self::Capacity(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for MutationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CapacityError> for MutationError {
+    fn from(value: CapacityError) -> Self {
+        Self::Capacity(value)
+    }
+}
+
+/// Error returned when decoding a text with [`DelimitedCodec`](crate::delimited::DelimitedCodec).
+///
+/// Positions are byte offsets into the decoded text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelimitedError {
+    /// Escape character appeared at the end of the text.
+    DanglingEscape { position: usize },
+    /// Escape character is followed by a character which doesn't need escaping.
+    InvalidEscape { position: usize, found: char },
+    /// Quoted token is not closed until the end of the text.
+    UnterminatedQuote { position: usize },
+    /// Quote character appeared in the middle of a token, or a closing quote is not followed by a
+    /// delimiter.
+    UnexpectedQuote { position: usize },
+    /// Decoded sequence doesn't fit into [`Offset`](crate::Offset).
+    Capacity(CapacityError),
+}
+
+impl fmt::Display for DelimitedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DanglingEscape { position } => write!(f, "dangling escape at {position}"),
+            Self::InvalidEscape { position, found } => {
+                write!(f, "invalid escape of {found:?} at {position}")
+            }
+            Self::UnterminatedQuote { position } => {
+                write!(f, "quote opened at {position} is not terminated")
+            }
+            Self::UnexpectedQuote { position } => write!(f, "unexpected quote at {position}"),
+            Self::Capacity(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DelimitedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CapacityError> for DelimitedError {
+    fn from(value: CapacityError) -> Self {
+        Self::Capacity(value)
+    }
+}
+
+/// Error returned when compiling a [`SequencePattern`](crate::pattern::SequencePattern).
+///
+/// `token` is the index of the offending pattern token, and `position` is a byte offset into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// Escape character appeared at the end of a token.
+    DanglingEscape { token: usize, position: usize },
+    /// Character class opened by `[` is not closed until the end of a token.
+    UnterminatedClass { token: usize, position: usize },
+    /// Character range of a class is reversed, e.g. `[z-a]`.
+    InvalidRange { token: usize, position: usize },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DanglingEscape { token, position } => {
+                write!(f, "dangling escape at {position} of token {token}")
+            }
+            Self::UnterminatedClass { token, position } => {
+                write!(f, "class opened at {position} of token {token} is not terminated")
+            }
+            Self::InvalidRange { token, position } => {
+                write!(f, "invalid range at {position} of token {token}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Error returned when validating a [`TopicFilter`](crate::topic::TopicFilter).
+///
+/// `token` is the index of the offending filter token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicFilterError {
+    /// Filter has no token.
+    Empty,
+    /// `+` or `#` appeared as a part of a token, e.g. `a+`.
+    InvalidWildcard { token: usize },
+    /// `#` appeared before the last token.
+    MisplacedTail { token: usize },
+}
+
+impl fmt::Display for TopicFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("topic filter is empty"),
+            Self::InvalidWildcard { token } => write!(f, "wildcard in the middle of token {token}"),
+            Self::MisplacedTail { token } => write!(f, "'#' at token {token} is not the last"),
+        }
+    }
+}
+
+impl std::error::Error for TopicFilterError {}
+
+/// Error returned when inserting a route into a [`RouteTable`](crate::route::RouteTable).
+///
+/// `token` is the index of the offending route token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    /// `:` or `*` is not followed by a parameter name.
+    EmptyName { token: usize },
+    /// Parameter name is already used by a previous token of the same route.
+    DuplicateName { token: usize },
+    /// Catch-all `*name` appeared before the last token.
+    MisplacedCatchAll { token: usize },
+    /// Route has the same shape as an existing one, differing only by parameter names if any, or
+    /// has `:name` where an existing one has `*name` after the same tokens, or vice versa.
+    Conflict,
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyName { token } => write!(f, "parameter at token {token} has no name"),
+            Self::DuplicateName { token } => {
+                write!(f, "parameter name at token {token} is already used")
+            }
+            Self::MisplacedCatchAll { token } => {
+                write!(f, "catch-all at token {token} is not the last")
+            }
+            Self::Conflict => f.write_str("route conflicts with an existing one"),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// Error returned when applying an edit script with
+/// [`MutableStringSequence::try_apply_patch`](crate::MutableStringSequence::try_apply_patch).
+///
+/// `step` is the index of the offending step in the script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// `old` range doesn't start where the previous step ended, or exceeds the patched sequence.
+    OldRange { step: usize },
+    /// `new` range exceeds the new sequence.
+    NewRange { step: usize },
+    /// Ranges don't agree with the kind, e.g. an insertion whose `old` range is not empty.
+    KindMismatch { step: usize },
+    /// Patched sequence doesn't fit into [`Offset`](crate::Offset).
+    Capacity(CapacityError),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OldRange { step } => write!(f, "old range of step {step} is out of place"),
+            Self::NewRange { step } => write!(f, "new range of step {step} is out of bounds"),
+            Self::KindMismatch { step } => write!(f, "ranges of step {step} don't match its kind"),
+            Self::Capacity(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CapacityError> for PatchError {
+    fn from(value: CapacityError) -> Self {
+        Self::Capacity(value)
+    }
+}
+
+/// Error returned when decoding a sequence from its binary representation.
+///
+/// Positions are byte offsets into the decoded input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Input ended in the middle of a sequence.
+    UnexpectedEnd { position: usize },
+    /// Byte which is not allowed at the position.
+    InvalidByte { position: usize, found: u8 },
+    /// Token starting at the position is not valid UTF-8.
+    InvalidUtf8 { position: usize },
+    /// Format version is not supported by this build.
+    UnsupportedVersion { version: u8 },
+    /// Input continues after a complete sequence.
+    TrailingBytes { position: usize },
+    /// Token boundary at the position is out of order.
+    InvalidOffset { position: usize },
+    /// Boundaries of the input are not as wide as expected, e.g. an aligned layout written by a
+    /// build with a different [`Offset`](crate::Offset) width.
+    OffsetWidth { expected: u8, found: u8 },
+    /// Input is not aligned to borrow its boundaries in place.
+    Misaligned { alignment: usize },
+    /// Little-endian input can't be borrowed in place on a big-endian host.
+    UnsupportedEndian,
+    /// Decoded sequence doesn't fit into [`Offset`](crate::Offset).
+    Capacity(CapacityError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd { position } => write!(f, "unexpected end of input at {position}"),
+            Self::InvalidByte { position, found } => {
+                write!(f, "invalid byte {found:#04x} at {position}")
+            }
+            Self::InvalidUtf8 { position } => write!(f, "token at {position} is not valid UTF-8"),
+            Self::UnsupportedVersion { version } => write!(f, "unsupported version {version}"),
+            Self::TrailingBytes { position } => write!(f, "trailing bytes at {position}"),
+            Self::InvalidOffset { position } => write!(f, "invalid token boundary at {position}"),
+            Self::OffsetWidth { expected, found } => {
+                write!(f, "boundary width {found} doesn't match expected {expected}")
+            }
+            Self::Misaligned { alignment } => write!(f, "input is not aligned to {alignment}"),
+            Self::UnsupportedEndian => f.write_str("host is not little-endian"),
+            Self::Capacity(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CapacityError> for DecodeError {
+    fn from(value: CapacityError) -> Self {
+        Self::Capacity(value)
+    }
+}
@@ -0,0 +1,212 @@
+//! Deduplication of shared sequences.
+
+use std::collections::{hash_map::RandomState, HashMap};
+use std::hash::BuildHasher;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::{view::WeakSequence, SharedStringSequence, StringSequenceRef, StringSequenceView};
+
+/// Minimum number of entries to trigger an automatic purge.
+const MIN_PURGE_THRESHOLD: usize = 64;
+
+/// Thread-safe interner, which returns the same [`SharedStringSequence`] buffer for equal
+/// contents. Interned sequences can be compared by [`SharedStringSequence::ptr_eq`].
+///
+/// Entries are [`std::sync::Weak`] references by default, so an entry dies as soon as the last
+/// sequence handed out for it is dropped, and is never returned again. The allocation of a dead
+/// entry is returned once the entry is swept, which happens on lookups of a colliding hash, as the
+/// interner grows, and by [`Self::purge`]. Entries interned by [`Self::intern_pinned`] are held
+/// strongly, and never die.
+///
+/// ```
+/// use strseq::{interner::SequenceInterner, SharedStringSequence, StringSequence};
+///
+/// let interner = SequenceInterner::new();
+/// let a = interner.intern(&StringSequence::parse_delimited("a/b", '/'));
+/// let b = interner.intern(&["a", "b"].into_iter().collect::<StringSequence>());
+/// assert!(SharedStringSequence::ptr_eq(&a, &b));
+///
+/// drop((a, b));
+/// assert_eq!(interner.purge(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct SequenceInterner {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Interned sequences by hash of their contents. Entries whose hashes collide share a bucket.
+    buckets: HashMap<u64, Vec<Entry>>,
+    hasher: RandomState,
+    /// Number of entries of every bucket, including dead ones not swept yet.
+    len: usize,
+    stats: InternerStats,
+    /// Number of entries which triggers the next automatic purge.
+    purge_at: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    seq: WeakSequence,
+    /// Strong reference, which keeps a pinned entry alive.
+    pinned: Option<SharedStringSequence>,
+}
+
+/// Statistics of a [`SequenceInterner`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InternerStats {
+    /// Number of entries currently interned, including dead ones not swept yet.
+    pub entries: usize,
+    /// Number of lookups which found an existing entry.
+    pub hits: u64,
+    /// Number of lookups which created a new entry.
+    pub misses: u64,
+    /// Number of entries purged so far.
+    pub purged: u64,
+}
+
+impl SequenceInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide interner.
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<SequenceInterner> = OnceLock::new();
+        GLOBAL.get_or_init(Self::new)
+    }
+
+    /// Returns the canonical sequence equal to `seq`, copying it into a new entry if there's
+    /// none yet.
+    pub fn intern<T: StringSequenceView + ?Sized>(&self, seq: &T) -> SharedStringSequence {
+        self.insert(seq, false, |x| x.into())
+    }
+
+    /// Same as [`Self::intern`], but reuses the buffer of `seq` for a new entry, if it's not a
+    /// subsequence of a larger buffer.
+    pub fn intern_shared(&self, seq: SharedStringSequence) -> SharedStringSequence {
+        self.insert(seq, false, |seq| {
+            if seq.is_full_sequence() {
+                seq
+            } else {
+                SharedStringSequence::from(&seq)
+            }
+        })
+    }
+
+    /// Same as [`Self::intern`], but the entry is never purged. An existing weak entry is pinned.
+    pub fn intern_pinned<T: StringSequenceView + ?Sized>(&self, seq: &T) -> SharedStringSequence {
+        self.insert(seq, true, |x| x.into())
+    }
+
+    /// Looks up `seq`, or creates a new entry from it by `create`.
+    fn insert<S>(
+        &self,
+        seq: S,
+        pinned: bool,
+        create: impl FnOnce(S) -> SharedStringSequence,
+    ) -> SharedStringSequence
+    where
+        S: std::ops::Deref,
+        S::Target: StringSequenceView,
+    {
+        let mut state = self.lock();
+        let key = seq.as_seq_ref();
+        let hash = state.hasher.hash_one(key);
+
+        if let Some(entry) = state.find(hash, &key) {
+            if pinned && entry.pinned.is_none() {
+                entry.pinned = entry.seq.upgrade();
+            }
+
+            // `find` skips dead entries, and the lock keeps the entry from dying in the meantime
+            // unless it's dropped by another thread; then create a new one.
+            if let Some(interned) = entry.seq.upgrade() {
+                state.stats.hits += 1;
+                return interned;
+            }
+        }
+
+        if state.len >= state.purge_at {
+            state.purge();
+            state.purge_at = MIN_PURGE_THRESHOLD.max(state.len * 2);
+        }
+
+        let interned = create(seq);
+        let entry = Entry { seq: interned.downgrade(), pinned: pinned.then(|| interned.clone()) };
+        state.buckets.entry(hash).or_default().push(entry);
+        state.len += 1;
+        state.stats.misses += 1;
+        interned
+    }
+
+    /// Finds the canonical sequence equal to `seq`, without creating an entry.
+    pub fn get<T: StringSequenceView + ?Sized>(&self, seq: &T) -> Option<SharedStringSequence> {
+        let mut state = self.lock();
+        let key = seq.as_seq_ref();
+        let hash = state.hasher.hash_one(key);
+        state.find(hash, &key)?.seq.upgrade()
+    }
+
+    /// Sweeps dead entries, and returns the number of swept entries.
+    pub fn purge(&self) -> usize {
+        self.lock().purge()
+    }
+
+    /// Number of entries, including dead ones not swept yet.
+    pub fn len(&self) -> usize {
+        self.lock().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        let state = self.lock();
+        InternerStats { entries: state.len, ..state.stats }
+    }
+
+    /// Removes every entry, including pinned ones. Sequences returned earlier stay valid, but
+    /// won't be shared with ones interned later.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.buckets.clear();
+        state.len = 0;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Every update of the state is done without panicking in the middle.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    /// Finds the live entry equal to `key` in the bucket of `hash`, sweeping dead entries of the
+    /// bucket on the way.
+    fn find(&mut self, hash: u64, key: &StringSequenceRef<'_>) -> Option<&mut Entry> {
+        let bucket = self.buckets.get_mut(&hash)?;
+        let len = bucket.len();
+        bucket.retain(|x| !x.seq.is_dead());
+
+        let swept = len - bucket.len();
+        self.len -= swept;
+        self.stats.purged += swept as u64;
+
+        bucket.iter_mut().find(|x| x.seq.upgrade().is_some_and(|x| x == *key))
+    }
+
+    fn purge(&mut self) -> usize {
+        let len = self.len;
+        self.buckets.retain(|_, bucket| {
+            bucket.retain(|x| !x.seq.is_dead());
+            !bucket.is_empty()
+        });
+
+        self.len = self.buckets.values().map(Vec::len).sum();
+        let purged = len - self.len;
+        self.stats.purged += purged as u64;
+        purged
+    }
+}
@@ -1,24 +1,20 @@
-/// TODO: In future, replace all `Range<u32>` usage to `[u32]`, since every tokens are adjacently
-/// stored in memory, current implementation waste single word for each token to store duplicated
-/// index offset!
-
 macro_rules! impl_seq_view {
-    ($Type:ident) => {
+    ($Type:ident $(<$lt:lifetime>)?) => {
         /* ------------------------------------ Display Trait ----------------------------------- */
-        impl std::fmt::Debug for $Type {
+        impl$(<$lt>)? std::fmt::Debug for $Type$(<$lt>)? {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                <Self as crate::base_trait::StringSequenceView>::fmt_debug(self, f)
+                <Self as crate::base_trait::RawView>::fmt_debug(self, f)
             }
         }
 
-        impl std::fmt::Display for $Type {
+        impl$(<$lt>)? std::fmt::Display for $Type$(<$lt>)? {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                <Self as crate::base_trait::StringSequenceView>::fmt_display(self, f)
+                <Self as crate::base_trait::RawView>::fmt_display(self, f)
             }
         }
 
         /* ----------------------------------- Accessor Trait ----------------------------------- */
-        impl std::ops::Index<usize> for $Type {
+        impl$(<$lt>)? std::ops::Index<usize> for $Type$(<$lt>)? {
             type Output = str;
 
             fn index(&self, index: usize) -> &Self::Output {
@@ -27,132 +23,178 @@ macro_rules! impl_seq_view {
         }
 
         /* ----------------------------------- Iterator Trait ----------------------------------- */
-        impl std::hash::Hash for $Type {
+        #[cfg(not(feature = "cached-hash"))]
+        impl$(<$lt>)? std::hash::Hash for $Type$(<$lt>)? {
             fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
                 self.iter().for_each(|x| std::hash::Hash::hash(x, state))
             }
         }
 
+        #[cfg(feature = "cached-hash")]
+        impl$(<$lt>)? std::hash::Hash for $Type$(<$lt>)? {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                state.write_u64(<Self as crate::base_trait::RawView>::fingerprint(self))
+            }
+        }
+
         /* -------------------------------------- Comparing ------------------------------------- */
-        impl<T: crate::base_trait::StringSequenceView> PartialEq<T> for $Type {
+        impl<$($lt,)? T: crate::StringSequenceView + ?Sized> PartialEq<T> for $Type$(<$lt>)? {
             fn eq(&self, other: &T) -> bool {
-                self.iter().eq(other.iter())
+                crate::base_trait::is_same_view(self, other) || self.iter().eq(other.iter())
             }
         }
 
-        impl Eq for $Type {}
+        impl$(<$lt>)? Eq for $Type$(<$lt>)? {}
 
-        impl<T: crate::base_trait::StringSequenceView> PartialOrd<T> for $Type {
+        impl<$($lt,)? T: crate::StringSequenceView + ?Sized> PartialOrd<T> for $Type$(<$lt>)? {
             fn partial_cmp(&self, other: &T) -> Option<std::cmp::Ordering> {
+                if crate::base_trait::is_same_view(self, other) {
+                    return Some(std::cmp::Ordering::Equal);
+                }
+
                 self.iter().partial_cmp(other.iter())
             }
         }
 
-        impl Ord for $Type {
+        impl$(<$lt>)? Ord for $Type$(<$lt>)? {
             fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                if crate::base_trait::is_same_view(self, other) {
+                    return std::cmp::Ordering::Equal;
+                }
+
                 self.iter().cmp(other.iter())
             }
         }
 
         /* ---------------------------------------- Refs ---------------------------------------- */
-        impl AsRef<str> for $Type {
+        impl$(<$lt>)? AsRef<str> for $Type$(<$lt>)? {
             fn as_ref(&self) -> &str {
                 self.text()
             }
         }
 
-        impl AsRef<[u8]> for $Type {
+        impl$(<$lt>)? AsRef<[u8]> for $Type$(<$lt>)? {
             fn as_ref(&self) -> &[u8] {
                 self.text().as_bytes()
             }
         }
 
-        impl AsRef<std::path::Path> for $Type {
+        impl$(<$lt>)? AsRef<std::path::Path> for $Type$(<$lt>)? {
             fn as_ref(&self) -> &std::path::Path {
                 std::path::Path::new(self.text())
             }
         }
 
-        impl AsRef<std::ffi::OsStr> for $Type {
+        impl$(<$lt>)? AsRef<std::ffi::OsStr> for $Type$(<$lt>)? {
             fn as_ref(&self) -> &std::ffi::OsStr {
                 std::ffi::OsStr::new(self.text())
             }
         }
 
         /* -------------------------------------- Type Impl ------------------------------------- */
-        impl $Type {
-            fn tokens(&self) -> &[std::ops::Range<u32>] {
-                let (_, index) = self.inner();
-                index
+        // NOTE: Inherent forwarders of `StringSequenceView`, so that the trait doesn't need to be
+        // imported for casual use.
+        impl$(<$lt>)? $Type$(<$lt>)? {
+            pub fn iter(&self) -> crate::StringSequenceIter<'_> {
+                <Self as crate::StringSequenceView>::iter(self)
             }
 
-            pub fn iter(&self) -> crate::base_trait::StringSequenceIter {
-                <Self as crate::base_trait::StringSequenceView>::iter(self)
-            }
-
-            pub fn slice(
-                &self,
-                range: impl crate::base_trait::ToRange,
-            ) -> crate::base_trait::StringSequenceIter {
-                <Self as crate::base_trait::StringSequenceView>::slice(self, range)
+            pub fn slice(&self, range: impl crate::ToRange) -> crate::StringSequenceIter<'_> {
+                <Self as crate::StringSequenceView>::slice(self, range)
             }
 
             pub fn get(&self, index: usize) -> Option<&str> {
-                self.iter().nth(index)
+                <Self as crate::StringSequenceView>::get(self, index)
             }
 
             pub fn text(&self) -> &str {
-                <Self as crate::base_trait::StringSequenceView>::text(self)
+                <Self as crate::StringSequenceView>::text(self)
             }
 
             pub fn first(&self) -> Option<&str> {
-                self.get(0)
+                <Self as crate::StringSequenceView>::first(self)
             }
 
             pub fn last(&self) -> Option<&str> {
-                self.get(self.tokens().len().saturating_sub(1))
+                <Self as crate::StringSequenceView>::last(self)
             }
 
             pub fn len(&self) -> usize {
-                self.tokens().len()
+                <Self as crate::StringSequenceView>::len(self)
             }
 
             pub fn is_empty(&self) -> bool {
-                self.len() == 0
+                <Self as crate::StringSequenceView>::is_empty(self)
             }
 
             pub fn starts_with(&self, other: &[impl AsRef<str>]) -> bool {
-                self.iter().zip(other.iter()).all(|(a, b)| a == b.as_ref())
+                <Self as crate::StringSequenceView>::starts_with(self, other)
             }
 
             pub fn ends_with(&self, other: &[impl AsRef<str>]) -> bool {
-                self.iter().rev().zip(other.iter().rev()).all(|(a, b)| a == b.as_ref())
+                <Self as crate::StringSequenceView>::ends_with(self, other)
             }
 
             pub fn contains(&self, other: &[impl AsRef<str>]) -> bool {
-                let mut iter = self.iter();
+                <Self as crate::StringSequenceView>::contains(self, other)
+            }
 
-                if other.is_empty() {
-                    return true;
-                }
+            pub fn as_seq_ref(&self) -> crate::StringSequenceRef<'_> {
+                <Self as crate::StringSequenceView>::as_seq_ref(self)
+            }
 
-                loop {
-                    if iter.len() < other.len() {
-                        break false;
-                    }
+            pub fn slice_ref(&self, range: impl crate::ToRange) -> crate::StringSequenceRef<'_> {
+                <Self as crate::StringSequenceView>::slice_ref(self, range)
+            }
 
-                    if iter.clone().take(other.len()).eq(other.iter().map(|x| x.as_ref())) {
-                        break true;
-                    }
+            pub fn display_with<D: std::fmt::Display>(
+                &self,
+                delim: D,
+            ) -> crate::delimited::DisplayWith<'_, D> {
+                <Self as crate::StringSequenceView>::display_with(self, delim)
+            }
 
-                    iter.next();
-                }
+            pub fn find(&self, needle: &[impl AsRef<str>]) -> Option<usize> {
+                <Self as crate::StringSequenceView>::find(self, needle)
+            }
+
+            pub fn rfind(&self, needle: &[impl AsRef<str>]) -> Option<usize> {
+                <Self as crate::StringSequenceView>::rfind(self, needle)
+            }
+
+            pub fn find_iter<'__n, N: AsRef<str>>(
+                &self,
+                needle: &'__n [N],
+            ) -> crate::search::FindIter<'_, '__n, N> {
+                <Self as crate::StringSequenceView>::find_iter(self, needle)
+            }
+
+            pub fn replace(
+                &self,
+                from: &[impl AsRef<str>],
+                to: &[impl AsRef<str>],
+            ) -> crate::MutableStringSequence {
+                <Self as crate::StringSequenceView>::replace(self, from, to)
+            }
+
+            pub fn common_prefix_len<T: crate::StringSequenceView + ?Sized>(
+                &self,
+                other: &T,
+            ) -> usize {
+                <Self as crate::StringSequenceView>::common_prefix_len(self, other)
+            }
+
+            pub fn common_suffix_len<T: crate::StringSequenceView + ?Sized>(
+                &self,
+                other: &T,
+            ) -> usize {
+                <Self as crate::StringSequenceView>::common_suffix_len(self, other)
             }
         }
 
-        impl<'a> IntoIterator for &'a $Type {
-            type Item = &'a str;
-            type IntoIter = crate::base_trait::StringSequenceIter<'a>;
+        impl<'__r, $($lt)?> IntoIterator for &'__r $Type$(<$lt>)? {
+            type Item = &'__r str;
+            type IntoIter = crate::StringSequenceIter<'__r>;
 
             fn into_iter(self) -> Self::IntoIter {
                 self.iter()
@@ -161,9 +203,24 @@ macro_rules! impl_seq_view {
     };
 }
 
+pub mod arena;
 #[doc(hidden)]
 mod base_trait;
+pub mod binary;
+pub mod borrowed;
+pub mod corpus;
+pub mod delimited;
+pub mod diff;
+pub mod error;
+pub mod interner;
+pub mod memcomparable;
 pub mod mutable;
+pub mod path;
+pub mod pattern;
+pub mod route;
+pub mod search;
+pub mod topic;
+pub mod trie;
 pub mod view;
 
 #[cfg(feature = "serde")]
@@ -172,5 +229,11 @@ mod serde_impl;
 #[cfg(test)]
 mod tests;
 
+pub use base_trait::{Offset, StringSequenceIter, StringSequenceView, ToRange};
+pub use borrowed::{StrSeq, StringSequenceRef};
+pub use error::{
+    CapacityError, DecodeError, DelimitedError, MutationError, PatchError, PatternError,
+    RouteError, TopicFilterError,
+};
 pub use mutable::MutableStringSequence;
 pub use view::{SharedStringSequence, StringSequence};
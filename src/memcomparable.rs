@@ -0,0 +1,135 @@
+//! Order-preserving binary encoding, for keys of ordered byte-keyed stores.
+//!
+//! Each token is written as its bytes, where `0x00` is escaped as `0x00 0xFF`, followed by the
+//! terminator `0x00 0x01`. Therefore comparing encoded bytes gives the same order as comparing
+//! sequences, and the encoding of a sequence is a prefix of encodings of sequences which start
+//! with its tokens.
+//!
+//! ```
+//! use strseq::memcomparable::MemcomparableExt;
+//! use strseq::StringSequence;
+//!
+//! let seq = |x: &str| StringSequence::parse_delimited(x, '/');
+//! let (a, b, c) = (seq("a/b"), seq("a/b/c"), seq("ab"));
+//! assert!(a < b && b < c);
+//! assert!(a.encode_memcomparable() < b.encode_memcomparable());
+//! assert!(b.encode_memcomparable() < c.encode_memcomparable());
+//!
+//! let end = a.memcomparable_upper_bound().unwrap();
+//! assert!(b.encode_memcomparable() < end && end < c.encode_memcomparable());
+//! assert_eq!(StringSequence::decode_memcomparable(&b.encode_memcomparable()).unwrap(), b);
+//! ```
+
+use crate::base_trait::to_offset;
+use crate::{CapacityError, DecodeError, Offset, StringSequence, StringSequenceView};
+
+const ESCAPE: u8 = 0x00;
+const ESCAPED_ZERO: u8 = 0xFF;
+const TERMINATOR: u8 = 0x01;
+
+/// Memcomparable encoding of sequences, implemented for every [`StringSequenceView`].
+pub trait MemcomparableExt: StringSequenceView {
+    /// Encodes the sequence into bytes whose order is the same as the sequence's. See
+    /// [`crate::memcomparable`].
+    fn encode_memcomparable(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(encoded_len(self));
+        let Ok(()) = encode_with(self, |x| {
+            bytes.extend_from_slice(x);
+            Ok::<_, std::convert::Infallible>(())
+        });
+
+        bytes
+    }
+
+    /// Writes the same bytes as [`Self::encode_memcomparable`].
+    fn write_memcomparable(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        encode_with(self, |x| out.write_all(x))
+    }
+
+    /// Exclusive upper bound of encodings of every sequence which starts with this sequence's
+    /// tokens, including itself, to be used as the end of a range scan. The start of the range is
+    /// [`Self::encode_memcomparable`].
+    ///
+    /// Returns `None` for an empty sequence, as every sequence starts with it.
+    fn memcomparable_upper_bound(&self) -> Option<Vec<u8>> {
+        let mut bytes = self.encode_memcomparable();
+        // Bumps the terminator of the last token, which is greater than any continuation.
+        *bytes.last_mut()? += 1;
+        Some(bytes)
+    }
+}
+
+impl<T: StringSequenceView + ?Sized> MemcomparableExt for T {}
+
+/// Emits encoded chunks of the sequence in order.
+pub(crate) fn encode_with<T, E>(
+    seq: &T,
+    mut emit: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: StringSequenceView + ?Sized,
+{
+    for token in seq.iter() {
+        let mut chunks = token.as_bytes().split(|x| *x == 0);
+
+        // `split` yields at least one chunk, and one more for each zero.
+        emit(chunks.next().unwrap_or_default())?;
+        for chunk in chunks {
+            emit(&[ESCAPE, ESCAPED_ZERO])?;
+            emit(chunk)?;
+        }
+
+        emit(&[ESCAPE, TERMINATOR])?;
+    }
+
+    Ok(())
+}
+
+/// Exact length of the encoding.
+pub(crate) fn encoded_len<T: StringSequenceView + ?Sized>(seq: &T) -> usize {
+    let num_zeros = seq.text().bytes().filter(|x| *x == 0).count();
+    seq.text().len() + num_zeros + 2 * seq.len()
+}
+
+impl StringSequence {
+    /// Decodes a sequence encoded by
+    /// [`MemcomparableExt::encode_memcomparable`]. The input should contain exactly one
+    /// sequence.
+    pub fn decode_memcomparable(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut text = Vec::with_capacity(bytes.len());
+        let mut index: Vec<Offset> = vec![0];
+        let mut token_position = 0;
+        let mut iter = bytes.iter().copied().enumerate();
+
+        while let Some((_, byte)) = iter.next() {
+            if byte != ESCAPE {
+                text.push(byte);
+                continue;
+            }
+
+            match iter.next() {
+                Some((_, ESCAPED_ZERO)) => text.push(0),
+                Some((position, TERMINATOR)) => {
+                    let start = index[index.len() - 1] as usize;
+                    if std::str::from_utf8(&text[start..]).is_err() {
+                        return Err(DecodeError::InvalidUtf8 { position: token_position });
+                    }
+
+                    to_offset(index.len()).ok_or(CapacityError::TokenOverflow)?;
+                    index.push(to_offset(text.len()).ok_or(CapacityError::TextOverflow)?);
+                    token_position = position + 1;
+                }
+                Some((position, found)) => {
+                    return Err(DecodeError::InvalidByte { position, found })
+                }
+                None => return Err(DecodeError::UnexpectedEnd { position: bytes.len() }),
+            }
+        }
+
+        if token_position != bytes.len() {
+            return Err(DecodeError::UnexpectedEnd { position: bytes.len() });
+        }
+
+        Ok(Self::from_owned_index(index, &text))
+    }
+}
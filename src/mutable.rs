@@ -1,20 +1,27 @@
 use crate::{
-    base_trait::{retr, up, StringSequenceView, ToRange},
-    view::SharedStringSequence,
-    StringSequence,
+    base_trait::{retr, to_offset, up, Offset, RawView, StringSequenceView, ToRange},
+    view::{rebased_inner, SharedStringSequence},
+    CapacityError, MutationError, StringSequence,
 };
 
 /// A sequence of strings. This is used to represent a path.
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct MutableStringSequence {
     /// Internal buffer, to represent the sequence of strings.
-    text: Vec<u8>,
-    /// Offsets of the strings in the buffer.
-    index: Vec<std::ops::Range<u32>>,
+    pub(crate) text: Vec<u8>,
+    /// Boundaries of the strings in the buffer. Always starts with zero, and ends with the length
+    /// of the text buffer.
+    pub(crate) index: Vec<Offset>,
 }
 
-impl StringSequenceView for MutableStringSequence {
-    fn inner(&self) -> (&[u8], &[std::ops::Range<u32>]) {
+impl Default for MutableStringSequence {
+    fn default() -> Self {
+        Self { text: Vec::new(), index: vec![0] }
+    }
+}
+
+impl RawView for MutableStringSequence {
+    fn inner(&self) -> (&[u8], &[Offset]) {
         (&self.text, &self.index)
     }
 }
@@ -34,12 +41,14 @@ impl MutableStringSequence {
 
     /// Create new instance with capacities
     pub fn with_capacity(num_tokens: usize, num_chars: usize) -> Self {
-        Self { text: Vec::with_capacity(num_chars), index: Vec::with_capacity(num_tokens) }
+        let mut index = Vec::with_capacity(num_tokens + 1);
+        index.push(0);
+        Self { text: Vec::with_capacity(num_chars), index }
     }
 
     /// Token array capacity
     pub fn token_capacity(&self) -> usize {
-        self.index.capacity()
+        self.index.capacity() - 1
     }
 
     /// Text buffer capacity
@@ -60,29 +69,79 @@ impl MutableStringSequence {
         self.index.reserve(num_tokens);
     }
 
+    /// Checks if given number of tokens and bytes can be appended without overflowing
+    /// [`Offset`].
+    fn check_capacity(&self, num_tokens: usize, num_chars: usize) -> Result<(), CapacityError> {
+        let fits = |x: Option<usize>| x.and_then(to_offset).is_some();
+
+        if !fits(self.len().checked_add(num_tokens)) {
+            Err(CapacityError::TokenOverflow)
+        } else if !fits(self.text.len().checked_add(num_chars)) {
+            Err(CapacityError::TextOverflow)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Add list of references to the internal buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length or the number of tokens overflows [`Offset`].
     pub fn extend_from_slice<T: AsRef<str>>(&mut self, slice: &[T]) {
-        let buffer_len = slice.iter().fold(0, |acc, s| acc + s.as_ref().len());
+        self.try_extend_from_slice(slice).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::extend_from_slice`]. Nothing is appended on failure.
+    pub fn try_extend_from_slice<T: AsRef<str>>(
+        &mut self,
+        slice: &[T],
+    ) -> Result<(), CapacityError> {
+        let buffer_len = slice
+            .iter()
+            .try_fold(0usize, |acc, s| acc.checked_add(s.as_ref().len()))
+            .ok_or(CapacityError::TextOverflow)?;
+
+        self.check_capacity(slice.len(), buffer_len)?;
         self.reserve_buffer(buffer_len);
         self.reserve_index(slice.len());
 
-        let mut offset = self.text.len();
         for s in slice {
-            let s = s.as_ref();
-            self.index.push(offset as _..(offset + s.len()) as _);
-            self.text.extend_from_slice(s.as_bytes());
-            offset = self.text.len();
+            self.text.extend_from_slice(s.as_ref().as_bytes());
+            self.index.push(self.text.len() as Offset);
         }
+
+        Ok(())
     }
 
     /// Append a string to the internal buffer. As we can't pre-calculate required space for
     /// text buffer, this is inherently inefficient compared to [`Self::extend_from_slice`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length or the number of tokens overflows [`Offset`].
     pub fn extend<T: AsRef<str>>(&mut self, into_iter: impl IntoIterator<Item = T>) {
+        self.try_extend(into_iter).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::extend`]. Nothing is appended on failure.
+    pub fn try_extend<T: AsRef<str>>(
+        &mut self,
+        into_iter: impl IntoIterator<Item = T>,
+    ) -> Result<(), CapacityError> {
         let iter = into_iter.into_iter();
         let num_elem_hint = iter.size_hint().0;
+        let prev_len = self.len();
 
         self.reserve_index(num_elem_hint);
-        iter.for_each(|s| self.push_back(&s));
+        for s in iter {
+            if let Err(e) = self.try_push_back(&s) {
+                self.drain(prev_len..);
+                return Err(e);
+            }
+        }
+
+        Ok(())
     }
 
     /// Remove the string at the specified index.
@@ -91,70 +150,124 @@ impl MutableStringSequence {
     ///
     /// Panics if the index is out of bounds.
     pub fn remove(&mut self, index: usize) {
-        let range = self.index.remove(index);
-        self.index[index..].iter_mut().for_each(|x| {
-            x.start -= range.len() as u32;
-            x.end -= range.len() as u32;
-        });
-        self.text.drain(up(range));
+        self.try_remove(index).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::remove`].
+    pub fn try_remove(&mut self, index: usize) -> Result<(), MutationError> {
+        if index >= self.len() {
+            return Err(MutationError::OutOfBounds { index, len: self.len() });
+        }
+
+        let begin = self.index[index];
+        let end = self.index.remove(index + 1);
+        self.index[index + 1..].iter_mut().for_each(|x| *x -= end - begin);
+        self.text.drain(up(begin..end));
+
+        Ok(())
     }
 
     /// Remove the last string quickly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence is empty.
     pub fn pop_back(&mut self) {
-        let range = self.index.pop().unwrap();
-        self.text.drain(up(range));
+        self.try_pop_back().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::pop_back`].
+    pub fn try_pop_back(&mut self) -> Result<(), MutationError> {
+        if self.is_empty() {
+            return Err(MutationError::Empty);
+        }
+
+        self.index.pop();
+        self.text.truncate(*self.index.last().unwrap() as usize);
+
+        Ok(())
     }
 
     /// Append a string to the end of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length or the number of tokens overflows [`Offset`].
     pub fn push_back(&mut self, value: impl AsRef<str>) {
-        let value = value.as_ref();
-        let offset = self.text.len();
-        self.index.push(offset as _..(offset + value.len()) as _);
-        self.text.extend_from_slice(value.as_bytes());
+        self.try_push_back(value).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::push_back`].
+    pub fn try_push_back(&mut self, value: impl AsRef<str>) -> Result<(), CapacityError> {
+        let value = value.as_ref().as_bytes();
+        self.check_capacity(1, value.len())?;
+
+        self.text.extend_from_slice(value);
+        self.index.push(self.text.len() as Offset);
+
+        Ok(())
     }
 
     /// Insert a string at the specified index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds, or the text length or the number of tokens overflows
+    /// [`Offset`].
     pub fn insert(&mut self, index: usize, value: impl AsRef<str>) {
+        self.try_insert(index, value).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Fallible version of [`Self::insert`].
+    pub fn try_insert(
+        &mut self,
+        index: usize,
+        value: impl AsRef<str>,
+    ) -> Result<(), MutationError> {
+        if index > self.len() {
+            return Err(MutationError::OutOfBounds { index, len: self.len() });
+        }
+
         let value = value.as_ref().as_bytes();
-        let insert_offset = self.index.get(index).map(|x| x.start).unwrap_or(self.text.len() as _);
-        let offset = insert_offset as usize;
+        let offset = self.index[index];
+        self.check_capacity(1, value.len())?;
 
-        self.index[index..].iter_mut().for_each(|x| {
-            x.start += value.len() as u32;
-            x.end += value.len() as u32;
-        });
-        self.index.insert(index, offset as _..(offset + value.len()) as _);
+        self.index[index + 1..].iter_mut().for_each(|x| *x += value.len() as Offset);
+        self.index.insert(index + 1, offset + value.len() as Offset);
 
+        let offset = offset as usize;
         self.text.splice(offset..offset, value.iter().copied());
+
+        Ok(())
     }
 
     pub fn clear(&mut self) {
         self.text.clear();
-        self.index.clear();
+        self.index.truncate(1);
     }
 
-    pub fn drain(&mut self, range: impl ToRange) -> Drain {
+    pub fn drain(&mut self, range: impl ToRange) -> Drain<'_> {
         let self_ptr = self as *mut _;
 
-        let range = range.to_range(self.index.len());
+        let range = range.to_range(self.len());
 
         if range.is_empty() {
             // Early return if the range is empty
-            return Drain { inner: self_ptr, iter: self.index.drain(0..0), text_erase_range: 0..0 };
+            let iter = self.index.drain(0..0);
+            return Drain { inner: self_ptr, iter, cursor: 0, text_erase_range: 0..0 };
         }
 
-        let begin = self.index[range.start].start;
-        let end = self.index[range.end - 1].end;
+        let begin = self.index[range.start];
+        let end = self.index[range.end];
 
         // Subtract later element's offset before we process draining
         let removed_text_len = end - begin;
-        self.index[range.end..].iter_mut().for_each(|x| {
-            x.start -= removed_text_len;
-            x.end -= removed_text_len;
-        });
+        self.index[range.end + 1..].iter_mut().for_each(|x| *x -= removed_text_len);
 
-        let drain_iter = self.index.drain(range);
-        Drain { inner: self_ptr, iter: drain_iter, text_erase_range: begin..end }
+        // Boundaries after the first token's start are removed; the first one is kept as the start
+        // of the token which will come next.
+        let drain_iter = self.index.drain(range.start + 1..range.end + 1);
+        Drain { inner: self_ptr, iter: drain_iter, cursor: begin, text_erase_range: begin..end }
     }
 
     pub fn into_string_sequence(self) -> StringSequence {
@@ -166,8 +279,9 @@ impl MutableStringSequence {
 
 pub struct Drain<'a> {
     inner: *mut MutableStringSequence,
-    text_erase_range: std::ops::Range<u32>,
-    iter: std::vec::Drain<'a, std::ops::Range<u32>>,
+    text_erase_range: std::ops::Range<Offset>,
+    cursor: Offset,
+    iter: std::vec::Drain<'a, Offset>,
 }
 
 impl<'a> Drop for Drain<'a> {
@@ -181,8 +295,11 @@ impl<'a> Iterator for Drain<'a> {
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let end = self.iter.next()?;
+        let begin = std::mem::replace(&mut self.cursor, end);
+
         // SAFETY: We won't touch the `self.index` here, which is mutably borrowed for `iter`
-        self.iter.next().map(|range| unsafe { retr(&(*self.inner).text, range) })
+        Some(unsafe { retr(&(*self.inner).text, begin..end) })
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -212,8 +329,8 @@ impl MutableStringSequence {
 
 impl From<String> for MutableStringSequence {
     fn from(value: String) -> Self {
-        let unique_index = 0..value.len() as u32;
-        Self { index: [unique_index].into(), text: value.into_bytes() }
+        let len = to_offset(value.len()).expect("text length overflows offset");
+        Self { index: vec![0, len], text: value.into_bytes() }
     }
 }
 
@@ -224,17 +341,17 @@ impl From<MutableStringSequence> for String {
     }
 }
 
-impl<'a, T: StringSequenceView> From<&'a T> for MutableStringSequence {
+impl<'a, T: StringSequenceView + ?Sized> From<&'a T> for MutableStringSequence {
     fn from(value: &'a T) -> Self {
         let (buffer, index) = value.inner();
-        Self { text: buffer.to_vec(), index: index.to_vec() }
+        let (index, text) = rebased_inner(buffer, index);
+        Self { text: text.to_vec(), index }
     }
 }
 
 impl From<StringSequence> for MutableStringSequence {
     fn from(value: StringSequence) -> Self {
-        let (buffer, index) = value.inner();
-        Self { text: buffer.to_vec(), index: index.to_vec() }
+        Self::from(&value)
     }
 }
 
@@ -256,7 +373,7 @@ impl From<SharedStringSequence> for MutableStringSequence {
     }
 }
 
-impl<'a, T: AsRef<str> + 'a> FromIterator<T> for StringSequence {
+impl<T: AsRef<str>> FromIterator<T> for StringSequence {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         MutableStringSequence::from_iter(iter).into()
     }
@@ -268,7 +385,7 @@ impl StringSequence {
     }
 }
 
-impl<'a, T: AsRef<str> + 'a> FromIterator<T> for SharedStringSequence {
+impl<T: AsRef<str>> FromIterator<T> for SharedStringSequence {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         MutableStringSequence::from_iter(iter).into()
     }
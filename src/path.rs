@@ -0,0 +1,276 @@
+//! Path semantics over sequences, where each token is a path component.
+//!
+//! Read-only helpers such as [`PathExt::file_name`] are provided for every view by [`PathExt`].
+
+use std::{iter::FusedIterator, path::Path};
+
+use crate::{
+    base_trait::{retr, Offset, StringSequenceView},
+    MutableStringSequence, SharedStringSequence, StringSequence,
+};
+
+pub(crate) const CURRENT_DIR: &str = ".";
+pub(crate) const PARENT_DIR: &str = "..";
+
+/// Whether the token denotes the root directory, e.g. `/`.
+pub(crate) fn is_root(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(std::path::is_separator)
+}
+
+/// Whether the token is either `.` or `..`.
+pub(crate) fn is_special(token: &str) -> bool {
+    token == CURRENT_DIR || token == PARENT_DIR
+}
+
+/// Splits file name into stem and extension, following the rule of [`std::path::Path`].
+pub(crate) fn split_extension(file_name: &str) -> (&str, Option<&str>) {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (file_name, None),
+    }
+}
+
+/// Path helpers which read a sequence, implemented for every [`StringSequenceView`].
+pub trait PathExt: StringSequenceView {
+    /// The last token, unless the sequence is empty or ends with `.` or `..`.
+    fn file_name(&self) -> Option<&str> {
+        self.last().filter(|x| !is_special(x))
+    }
+
+    /// [`Self::file_name`] without its extension. See [`std::path::Path::file_stem`].
+    fn file_stem(&self) -> Option<&str> {
+        self.file_name().map(|x| split_extension(x).0)
+    }
+
+    /// Extension of [`Self::file_name`]. See [`std::path::Path::extension`].
+    fn extension(&self) -> Option<&str> {
+        self.file_name().and_then(|x| split_extension(x).1)
+    }
+
+    /// Copy of this sequence whose `.` and `..` tokens are lexically resolved. See
+    /// [`MutableStringSequence::normalize`].
+    fn normalized(&self) -> MutableStringSequence {
+        let mut seq = MutableStringSequence::from(self);
+        seq.normalize();
+        seq
+    }
+
+    /// Copy of this sequence with [`Self::file_name`] replaced. See
+    /// [`MutableStringSequence::set_file_name`].
+    fn with_file_name(&self, file_name: impl AsRef<str>) -> MutableStringSequence {
+        let mut seq = MutableStringSequence::from(self);
+        seq.set_file_name(file_name);
+        seq
+    }
+
+    /// Copy of this sequence with [`Self::extension`] replaced. See
+    /// [`MutableStringSequence::set_extension`].
+    fn with_extension(&self, extension: impl AsRef<str>) -> MutableStringSequence {
+        let mut seq = MutableStringSequence::from(self);
+        seq.set_extension(extension);
+        seq
+    }
+
+    /// Lexical relative path from `base` to `self`, e.g. `["..", "..", "x", "y"]` from `a/b/c` to
+    /// `a/x/y`. Both sequences are expected to be [normalized](Self::normalized).
+    ///
+    /// Returns `None` if `base` has a `..` token after the common prefix, which can't be resolved
+    /// without the file system.
+    fn relative_to<T: StringSequenceView + ?Sized>(
+        &self,
+        base: &T,
+    ) -> Option<MutableStringSequence> {
+        let num_common = self.common_prefix_len(base);
+        if base.iter().skip(num_common).any(|x| x == PARENT_DIR) {
+            return None;
+        }
+
+        let mut seq = MutableStringSequence::new();
+        seq.extend((num_common..base.len()).map(|_| PARENT_DIR));
+        seq.extend(self.iter().skip(num_common));
+        Some(seq)
+    }
+
+    /// Joins tokens as path components with the platform separator, inverse of
+    /// [`MutableStringSequence::from_path`].
+    ///
+    /// Unlike `AsRef<Path>`, which views the concatenated [`StringSequenceView::text`], each token becomes a
+    /// component. As [`std::path::PathBuf::push`] does, a token which is an absolute path replaces
+    /// everything before it.
+    fn to_path_buf(&self) -> std::path::PathBuf {
+        let mut path = std::path::PathBuf::with_capacity(self.text().len() + self.len());
+        self.iter().for_each(|x| path.push(x));
+        path
+    }
+}
+
+impl<T: StringSequenceView + ?Sized> PathExt for T {}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           SHARED                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+impl SharedStringSequence {
+    /// Sequence without its last token, sharing the buffer. `None` if the sequence is empty.
+    pub fn parent(&self) -> Option<Self> {
+        (!self.is_empty()).then(|| self.narrow(..self.len() - 1))
+    }
+
+    /// Iterates over `self` and its parents, until the empty sequence.
+    pub fn ancestors(&self) -> Ancestors {
+        Ancestors { next: Some(self.clone()) }
+    }
+
+    /// Remainder after `prefix`, sharing the buffer. `None` if `self` doesn't start with `prefix`.
+    pub fn strip_prefix<T: StringSequenceView + ?Sized>(&self, prefix: &T) -> Option<Self> {
+        let is_prefix =
+            self.len() >= prefix.len() && self.slice_ref(..prefix.len()) == prefix.as_seq_ref();
+        is_prefix.then(|| self.narrow(prefix.len()..))
+    }
+
+    /// Remainder before `suffix`, sharing the buffer. `None` if `self` doesn't end with `suffix`.
+    pub fn strip_suffix<T: StringSequenceView + ?Sized>(&self, suffix: &T) -> Option<Self> {
+        let start = self.len().checked_sub(suffix.len())?;
+        (self.slice_ref(start..) == suffix.as_seq_ref()).then(|| self.narrow(..start))
+    }
+}
+
+/// Iterator over a sequence and its parents. Created by [`SharedStringSequence::ancestors`].
+#[derive(Debug, Clone)]
+pub struct Ancestors {
+    next: Option<SharedStringSequence>,
+}
+
+impl Iterator for Ancestors {
+    type Item = SharedStringSequence;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next.take()?;
+        self.next = next.parent();
+        Some(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.next.as_ref().map(|x| x.len() + 1).unwrap_or(0);
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Ancestors {}
+
+impl FusedIterator for Ancestors {}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           MUTABLE                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+impl MutableStringSequence {
+    /// Lexically resolves `.` and `..` tokens in place, without touching the file system.
+    ///
+    /// `.` is removed, and `..` removes itself with the preceding token. `..` right after the root
+    /// is removed alone, and `..` which can't be resolved otherwise, e.g. `["..", "a"]`, is kept.
+    pub fn normalize(&mut self) {
+        let mut num_kept = 0;
+        let mut begin = 0;
+
+        // Kept tokens are moved toward the front, which never overwrites unvisited tokens.
+        for read in 0..self.len() {
+            let (text, index) = (&mut self.text, &mut self.index);
+            let end = index[read + 1] as usize;
+            let token = &text[begin..end];
+            let prev = (num_kept > 0).then(|| retr(text, index[num_kept - 1]..index[num_kept]));
+
+            match (token, prev) {
+                (b".", _) => {}
+                (b"..", Some(prev)) if is_root(prev) => {} // Root has no parent
+                (b"..", Some(prev)) if prev != PARENT_DIR => num_kept -= 1,
+                _ => {
+                    let dst = index[num_kept] as usize;
+                    text.copy_within(begin..end, dst);
+                    index[num_kept + 1] = (dst + end - begin) as Offset;
+                    num_kept += 1;
+                }
+            }
+
+            begin = end;
+        }
+
+        self.index.truncate(num_kept + 1);
+        self.text.truncate(self.index[num_kept] as usize);
+    }
+
+    /// Replaces the last token with `file_name`. If there's no
+    /// [`file_name`](PathExt::file_name), `file_name` is appended instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length overflows [`Offset`].
+    pub fn set_file_name(&mut self, file_name: impl AsRef<str>) {
+        if self.file_name().is_some() {
+            self.pop_back();
+        }
+
+        self.push_back(file_name);
+    }
+
+    /// Replaces the extension of the last token, or removes it if `extension` is empty. Returns
+    /// `false` and does nothing if there's no [`file_name`](PathExt::file_name).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length overflows [`Offset`].
+    pub fn set_extension(&mut self, extension: impl AsRef<str>) -> bool {
+        let Some(stem) = self.file_stem() else { return false };
+        let extension = extension.as_ref();
+
+        let mut file_name = String::with_capacity(stem.len() + 1 + extension.len());
+        file_name.push_str(stem);
+
+        if !extension.is_empty() {
+            file_name.push('.');
+            file_name.push_str(extension);
+        }
+
+        self.pop_back();
+        self.push_back(file_name);
+        true
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                      STD PATH CONVERSION                                   */
+/* ------------------------------------------------------------------------------------------ */
+
+impl MutableStringSequence {
+    /// Stores each [`Component`](std::path::Component) of `path` as a token. Prefix and root
+    /// directory are stored as they appear, e.g. `["C:", "\\", "a"]` on Windows, or `["/", "a"]`
+    /// elsewhere.
+    ///
+    /// Returns `None` if the path is not valid UTF-8.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the text length or the number of tokens overflows [`Offset`].
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let mut this = Self::new();
+        for component in path.as_ref().components() {
+            this.push_back(component.as_os_str().to_str()?);
+        }
+
+        Some(this)
+    }
+}
+
+impl StringSequence {
+    /// See [`MutableStringSequence::from_path`].
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        MutableStringSequence::from_path(path).map(Into::into)
+    }
+}
+
+impl SharedStringSequence {
+    /// See [`MutableStringSequence::from_path`].
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        MutableStringSequence::from_path(path).map(Into::into)
+    }
+}
@@ -0,0 +1,297 @@
+//! Token-level glob patterns.
+
+use std::iter::Peekable;
+use std::str::{CharIndices, Chars};
+
+use crate::{PatternError, StringSequenceIter, StringSequenceRef, StringSequenceView};
+
+/// Compiled glob pattern over tokens of a sequence.
+///
+/// Each pattern token matches a single token, except for `**` which matches zero or more tokens:
+///
+/// - `**` matches any number of tokens. Spans matched by it can be retrieved by
+///   [`Self::captures`].
+/// - `*` matches any single token.
+/// - Otherwise, the token is a glob which matches a single token; `*` matches any string, `?`
+///   matches any character, `[a-z_]` matches a character in the class and `[!0-9]` a character
+///   not in the class. `\` escapes the next character.
+///
+/// ```
+/// use strseq::{pattern::SequencePattern, StringSequence};
+///
+/// let pattern = SequencePattern::parse("src/**/*.rs", '/').unwrap();
+/// assert!(pattern.matches(&StringSequence::parse_delimited("src/lib.rs", '/')));
+/// assert!(!pattern.matches(&StringSequence::parse_delimited("src/data.json", '/')));
+///
+/// let path = StringSequence::parse_delimited("src/a/b/mod.rs", '/');
+/// let captures = pattern.captures(&path).unwrap();
+/// assert!(captures[0].iter().eq(["a", "b"]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequencePattern {
+    segments: Box<[Segment]>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    /// `**`
+    AnyTokens,
+    /// `*`
+    AnyToken,
+    Literal(Box<str>),
+    Glob(Box<[GlobItem]>),
+}
+
+#[derive(Debug, Clone)]
+enum GlobItem {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyString,
+    /// `[...]`, or `[!...]` if negated.
+    Class {
+        negated: bool,
+        ranges: Box<[(char, char)]>,
+    },
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                         COMPILATION                                        */
+/* ------------------------------------------------------------------------------------------ */
+
+impl SequencePattern {
+    /// Compiles a pattern from its tokens.
+    pub fn new<I>(tokens: I) -> Result<Self, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let segments = tokens
+            .into_iter()
+            .enumerate()
+            .map(|(index, token)| Segment::compile(token.as_ref(), index))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { segments })
+    }
+
+    /// Compiles a pattern from a text, whose tokens are split by `delim` in the same way as
+    /// [`StringSequence::parse_delimited`](crate::StringSequence::parse_delimited).
+    pub fn parse(text: &str, delim: char) -> Result<Self, PatternError> {
+        Self::new(text.split(delim))
+    }
+
+    /// Number of `**` tokens, which is the number of captures on a match.
+    pub fn num_captures(&self) -> usize {
+        self.segments.iter().filter(|x| matches!(x, Segment::AnyTokens)).count()
+    }
+}
+
+impl Segment {
+    fn compile(token: &str, index: usize) -> Result<Self, PatternError> {
+        match token {
+            "**" => return Ok(Self::AnyTokens),
+            "*" => return Ok(Self::AnyToken),
+            _ if !token.contains(['*', '?', '[', '\\']) => return Ok(Self::Literal(token.into())),
+            _ => (),
+        }
+
+        let mut items = Vec::new();
+        let mut chars = token.char_indices().peekable();
+
+        while let Some((position, ch)) = chars.next() {
+            let item = match ch {
+                '*' => GlobItem::AnyString,
+                '?' => GlobItem::AnyChar,
+                '\\' => match chars.next() {
+                    Some((_, ch)) => GlobItem::Char(ch),
+                    None => return Err(PatternError::DanglingEscape { token: index, position }),
+                },
+                '[' => compile_class(&mut chars, index, position)?,
+                ch => GlobItem::Char(ch),
+            };
+
+            items.push(item);
+        }
+
+        Ok(Self::Glob(items.into()))
+    }
+
+    /// Checks if this segment matches a single token. `**` never does, as it's handled by the
+    /// sequence matcher.
+    fn matches(&self, token: &str) -> bool {
+        match self {
+            Self::AnyTokens => false,
+            Self::AnyToken => true,
+            Self::Literal(literal) => **literal == *token,
+            Self::Glob(items) => glob_matches(items, token),
+        }
+    }
+}
+
+/// Compiles a class of `token`th pattern token, after its opening `[` at `open`.
+fn compile_class(
+    chars: &mut Peekable<CharIndices<'_>>,
+    token: usize,
+    open: usize,
+) -> Result<GlobItem, PatternError> {
+    let negated = chars.next_if(|(_, x)| matches!(x, '!' | '^')).is_some();
+    let mut ranges = Vec::new();
+
+    loop {
+        let (position, mut start) =
+            chars.next().ok_or(PatternError::UnterminatedClass { token, position: open })?;
+
+        match start {
+            // `]` right after the opening is a literal.
+            ']' if !ranges.is_empty() => break,
+            '\\' => start = chars.next().ok_or(PatternError::DanglingEscape { token, position })?.1,
+            _ => (),
+        }
+
+        let mut end = start;
+        if let Some((_, '-')) = chars.peek() {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+
+            // Trailing `-` before `]` is a literal.
+            if let Some((_, ch)) = lookahead.next().filter(|(_, x)| *x != ']') {
+                if ch < start {
+                    return Err(PatternError::InvalidRange { token, position });
+                }
+
+                *chars = lookahead;
+                end = ch;
+            }
+        }
+
+        ranges.push((start, end));
+    }
+
+    Ok(GlobItem::Class { negated, ranges: ranges.into() })
+}
+
+impl GlobItem {
+    fn matches(&self, ch: char) -> bool {
+        match self {
+            Self::Char(x) => *x == ch,
+            Self::AnyChar => true,
+            Self::AnyString => false,
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|(start, end)| (*start..=*end).contains(&ch)) != *negated
+            }
+        }
+    }
+}
+
+/// Wildcard matching which backtracks only to the last `*`, as every `*` before it can't do
+/// better by consuming more.
+fn glob_matches(items: &[GlobItem], token: &str) -> bool {
+    let mut chars = token.chars();
+    let mut item = 0;
+    let mut star: Option<(usize, Chars<'_>)> = None;
+
+    loop {
+        if let Some(GlobItem::AnyString) = items.get(item) {
+            item += 1;
+            star = Some((item, chars.clone()));
+            continue;
+        }
+
+        let mut next = chars.clone();
+        let Some(ch) = next.next() else { break };
+
+        if items.get(item).is_some_and(|x| x.matches(ch)) {
+            item += 1;
+            chars = next;
+        } else if let Some((resume, consumed)) = &mut star {
+            // Let the last `*` consume one more character.
+            if consumed.next().is_none() {
+                return false;
+            }
+
+            item = *resume;
+            chars = consumed.clone();
+        } else {
+            return false;
+        }
+    }
+
+    items[item..].iter().all(|x| matches!(x, GlobItem::AnyString))
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                          MATCHING                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+impl SequencePattern {
+    /// Checks if the whole sequence matches this pattern.
+    pub fn matches<T: StringSequenceView + ?Sized>(&self, seq: &T) -> bool {
+        self.match_spans(seq.iter(), |_| ())
+    }
+
+    /// Matches the whole sequence, and returns subsequences matched by each `**` in order.
+    pub fn captures<'a, T: StringSequenceView + ?Sized>(
+        &self,
+        seq: &'a T,
+    ) -> Option<Vec<StringSequenceRef<'a>>> {
+        let mut spans = Vec::with_capacity(self.num_captures());
+        let seq = seq.as_seq_ref();
+
+        self.match_spans(seq.iter(), |x| spans.push(x))
+            .then(|| spans.into_iter().map(|x| seq.subsequence(x)).collect())
+    }
+
+    /// Same algorithm as [`glob_matches`], applied to tokens. `on_capture` is called with the span
+    /// of each `**` once the match is confirmed.
+    fn match_spans(
+        &self,
+        mut tokens: StringSequenceIter<'_>,
+        mut on_capture: impl FnMut(std::ops::Range<usize>),
+    ) -> bool {
+        let num_tokens = tokens.len();
+        let position = |x: &StringSequenceIter<'_>| num_tokens - x.len();
+
+        let mut spans = Vec::new();
+        let mut segment = 0;
+        let mut star: Option<(usize, StringSequenceIter<'_>)> = None;
+
+        loop {
+            if let Some(Segment::AnyTokens) = self.segments.get(segment) {
+                segment += 1;
+                spans.push(position(&tokens)..position(&tokens));
+                star = Some((segment, tokens.clone()));
+                continue;
+            }
+
+            let mut next = tokens.clone();
+            let Some(token) = next.next() else { break };
+
+            if self.segments.get(segment).is_some_and(|x| x.matches(token)) {
+                segment += 1;
+                tokens = next;
+            } else if let Some((resume, consumed)) = &mut star {
+                // Let the last `**` consume one more token.
+                if consumed.next().is_none() {
+                    return false;
+                }
+
+                spans.last_mut().unwrap().end = position(consumed);
+
+                segment = *resume;
+                tokens = consumed.clone();
+            } else {
+                return false;
+            }
+        }
+
+        // A `**` is consumed as soon as it's reached, so any segment left requires a token.
+        if segment != self.segments.len() {
+            return false;
+        }
+
+        spans.into_iter().for_each(&mut on_capture);
+        true
+    }
+}
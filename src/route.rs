@@ -0,0 +1,324 @@
+//! HTTP-style route table with named parameters.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::{RouteError, StringSequenceRef, StringSequenceView};
+
+/// Table of routes, each of which is a sequence of tokens:
+///
+/// - `:name` matches a single non-empty token.
+/// - `*name` matches every remaining token, including none. Allowed only as the last token.
+/// - Any other token matches itself.
+///
+/// When several routes match, static tokens win over `:name`, which wins over `*name`, compared
+/// from the first token. Routes which differ only by parameter names, or which have `:name` and
+/// `*name` after the same tokens, are rejected on insertion, as they'd be ambiguous.
+///
+/// ```
+/// use strseq::{route::RouteTable, StringSequence, StringSequenceView};
+///
+/// let route = |x: &str| StringSequence::parse_delimited(x, '/');
+///
+/// let mut table = RouteTable::new();
+/// table.insert(&route("users/:id"), "user").unwrap();
+/// table.insert(&route("users/me"), "me").unwrap();
+/// table.insert(&route("files/*path"), "files").unwrap();
+/// assert!(table.insert(&route("users/:name"), "conflict").is_err());
+/// assert!(table.insert(&route("users/*rest"), "conflict").is_err());
+///
+/// let path = route("users/42");
+/// let (value, params) = table.resolve(&path).unwrap();
+/// assert_eq!((*value, params.get("id")), ("user", Some("42")));
+///
+/// let path = route("files/a/b.txt");
+/// let (_, params) = table.resolve(&path).unwrap();
+/// assert_eq!(params.get_seq("path").unwrap().display_with('/').to_string(), "a/b.txt");
+/// ```
+pub struct RouteTable<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    route: Option<Route<V>>,
+    statics: BTreeMap<Box<str>, Node<V>>,
+    param: Option<Box<Node<V>>>,
+    catch_all: Option<Route<V>>,
+}
+
+/// Value of a route, with its parameter names in order of appearance.
+struct Route<V> {
+    names: Box<[Box<str>]>,
+    /// Whether the last name is of `*name`.
+    catch_all: bool,
+    value: V,
+}
+
+/// Token of a route.
+enum Shape<'a> {
+    Static(&'a str),
+    Param(&'a str),
+    CatchAll(&'a str),
+}
+
+impl<'a> Shape<'a> {
+    fn of(token: &'a str) -> Self {
+        if let Some(name) = token.strip_prefix(':') {
+            Self::Param(name)
+        } else if let Some(name) = token.strip_prefix('*') {
+            Self::CatchAll(name)
+        } else {
+            Self::Static(token)
+        }
+    }
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self { route: None, statics: BTreeMap::new(), param: None, catch_all: None }
+    }
+}
+
+impl<V> Node<V> {
+    fn is_empty(&self) -> bool {
+        self.route.is_none()
+            && self.statics.is_empty()
+            && self.param.is_none()
+            && self.catch_all.is_none()
+    }
+
+    fn slot_or_create<'a>(
+        &mut self,
+        mut shapes: impl Iterator<Item = Shape<'a>>,
+    ) -> &mut Option<Route<V>> {
+        match shapes.next() {
+            None => &mut self.route,
+            Some(Shape::CatchAll(_)) => &mut self.catch_all,
+            Some(Shape::Param(_)) => {
+                self.param.get_or_insert_with(Default::default).slot_or_create(shapes)
+            }
+            Some(Shape::Static(token)) => {
+                self.statics.entry(token.into()).or_default().slot_or_create(shapes)
+            }
+        }
+    }
+
+    /// Whether a route of given shape would take a `:name` where an existing route takes a
+    /// `*name` after the same tokens, or vice versa.
+    fn is_ambiguous<'a>(&self, mut shapes: impl Iterator<Item = Shape<'a>>) -> bool {
+        match shapes.next() {
+            None => false,
+            Some(Shape::CatchAll(_)) => self.param.is_some(),
+            Some(Shape::Param(_)) => {
+                self.catch_all.is_some()
+                    || self.param.as_ref().is_some_and(|x| x.is_ambiguous(shapes))
+            }
+            Some(Shape::Static(token)) => {
+                self.statics.get(token).is_some_and(|x| x.is_ambiguous(shapes))
+            }
+        }
+    }
+
+    /// Takes the route of given shape, pruning nodes which become empty.
+    fn take<'a>(&mut self, mut shapes: impl Iterator<Item = Shape<'a>>) -> Option<Route<V>> {
+        match shapes.next() {
+            None => self.route.take(),
+            Some(Shape::CatchAll(_)) => self.catch_all.take(),
+            Some(Shape::Param(_)) => {
+                let child = self.param.as_mut()?;
+                let route = child.take(shapes);
+
+                if child.is_empty() {
+                    self.param = None;
+                }
+
+                route
+            }
+            Some(Shape::Static(token)) => {
+                let child = self.statics.get_mut(token)?;
+                let route = child.take(shapes);
+
+                if child.is_empty() {
+                    self.statics.remove(token);
+                }
+
+                route
+            }
+        }
+    }
+
+    /// Finds the route matching tokens from `pos`, pushing spans of its parameters to `spans`.
+    /// Spans pushed by a failed branch are popped before trying the next one.
+    fn resolve(
+        &self,
+        path: &StringSequenceRef<'_>,
+        pos: usize,
+        spans: &mut Vec<Range<usize>>,
+    ) -> Option<&Route<V>> {
+        if let Some(token) = path.get(pos) {
+            if let Some(route) =
+                self.statics.get(token).and_then(|x| x.resolve(path, pos + 1, spans))
+            {
+                return Some(route);
+            }
+
+            if let Some(child) = self.param.as_ref().filter(|_| !token.is_empty()) {
+                spans.push(pos..pos + 1);
+
+                if let Some(route) = child.resolve(path, pos + 1, spans) {
+                    return Some(route);
+                }
+
+                spans.pop();
+            }
+        } else if let Some(route) = &self.route {
+            return Some(route);
+        }
+
+        let route = self.catch_all.as_ref()?;
+        spans.push(pos..path.len());
+        Some(route)
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           METHODS                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+impl<V> Default for RouteTable<V> {
+    fn default() -> Self {
+        Self { root: Node::default(), len: 0 }
+    }
+}
+
+impl<V> RouteTable<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of routes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Compiles and inserts a route. The value is dropped if the route is invalid or conflicts
+    /// with an existing one.
+    pub fn insert<T: StringSequenceView + ?Sized>(
+        &mut self,
+        route: &T,
+        value: V,
+    ) -> Result<(), RouteError> {
+        let mut names = Vec::<Box<str>>::new();
+
+        for (index, token) in route.iter().enumerate() {
+            let name = match Shape::of(token) {
+                Shape::Static(_) => continue,
+                Shape::CatchAll(_) if index + 1 != route.len() => {
+                    return Err(RouteError::MisplacedCatchAll { token: index })
+                }
+                Shape::Param(name) | Shape::CatchAll(name) => name,
+            };
+
+            if name.is_empty() {
+                return Err(RouteError::EmptyName { token: index });
+            } else if names.iter().any(|x| **x == *name) {
+                return Err(RouteError::DuplicateName { token: index });
+            }
+
+            names.push(name.into());
+        }
+
+        if self.root.is_ambiguous(route.iter().map(Shape::of)) {
+            return Err(RouteError::Conflict);
+        }
+
+        let slot = self.root.slot_or_create(route.iter().map(Shape::of));
+        if slot.is_some() {
+            return Err(RouteError::Conflict);
+        }
+
+        let catch_all = route.last().is_some_and(|x| matches!(Shape::of(x), Shape::CatchAll(_)));
+        *slot = Some(Route { names: names.into(), catch_all, value });
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the route of the same shape, ignoring parameter names.
+    pub fn remove<T: StringSequenceView + ?Sized>(&mut self, route: &T) -> Option<V> {
+        let route = self.root.take(route.iter().map(Shape::of))?;
+        self.len -= 1;
+        Some(route.value)
+    }
+
+    /// Finds the route matching given path, and returns its value with the parameters which
+    /// borrow from `path`.
+    pub fn resolve<'r, 'a, T: StringSequenceView + ?Sized>(
+        &'r self,
+        path: &'a T,
+    ) -> Option<(&'r V, Params<'r, 'a>)> {
+        let path = path.as_seq_ref();
+        let mut spans = Vec::new();
+        let route = self.root.resolve(&path, 0, &mut spans)?;
+
+        let params = Params { path, names: &route.names, catch_all: route.catch_all, spans };
+        Some((&route.value, params))
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           PARAMS                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Parameters of a resolved route, in order of appearance.
+#[derive(Debug, Clone)]
+pub struct Params<'r, 'a> {
+    path: StringSequenceRef<'a>,
+    names: &'r [Box<str>],
+    catch_all: bool,
+    spans: Vec<Range<usize>>,
+}
+
+impl<'r, 'a> Params<'r, 'a> {
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Token of a `:name` parameter. Returns `None` for a `*name` parameter; use
+    /// [`Self::get_seq`] instead.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        let index = self.position(name)?;
+        if self.catch_all && index + 1 == self.names.len() {
+            return None;
+        }
+
+        self.path.into_iter().nth(self.spans[index].start)
+    }
+
+    /// Tokens of a parameter, either `:name` or `*name`.
+    pub fn get_seq(&self, name: &str) -> Option<StringSequenceRef<'a>> {
+        self.position(name).map(|x| self.path.subsequence(self.spans[x].clone()))
+    }
+
+    /// Iterates over names and tokens of every parameter.
+    pub fn iter(&self) -> impl Iterator<Item = (&'r str, StringSequenceRef<'a>)> + '_ {
+        let names = self.names.iter().map(|x| &**x);
+        names.zip(self.spans.iter().map(|x| self.path.subsequence(x.clone())))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|x| **x == *name)
+    }
+}
@@ -0,0 +1,148 @@
+//! Token-level substring search.
+
+use std::iter::{Enumerate, FusedIterator};
+
+use crate::{SharedStringSequence, StringSequenceIter};
+
+/// Knuth-Morris-Pratt matcher over needle tokens, which visits each token of the haystack once.
+/// If `reverse`, the needle is matched from its last token, to be fed with a reversed haystack.
+pub(crate) struct Searcher<'n, N> {
+    needle: &'n [N],
+    reverse: bool,
+    /// Length of the longest proper prefix of `needle[..=i]`, which is also its suffix.
+    table: Box<[usize]>,
+}
+
+impl<'n, N: AsRef<str>> Searcher<'n, N> {
+    pub(crate) fn new(needle: &'n [N], reverse: bool) -> Self {
+        let mut this = Self { needle, reverse, table: vec![0; needle.len()].into() };
+
+        let mut matched = 0;
+        for i in 1..needle.len() {
+            matched = this.step(matched, this.token(i));
+            this.table[i] = matched;
+        }
+
+        this
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.needle.len()
+    }
+
+    fn token(&self, index: usize) -> &str {
+        let index = if self.reverse { self.needle.len() - 1 - index } else { index };
+        self.needle[index].as_ref()
+    }
+
+    /// Feeds a token while `matched` tokens of the needle are matched, and returns the new number
+    /// of matched tokens. A full match should be reset by the caller.
+    pub(crate) fn step(&self, mut matched: usize, token: &str) -> usize {
+        while matched > 0 && self.token(matched) != token {
+            matched = self.table[matched - 1];
+        }
+
+        if self.token(matched) == token {
+            matched + 1
+        } else {
+            0
+        }
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                          ITERATOR                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Iterator over token positions of non-overlapping matches of a needle, from the front. See
+/// [`StringSequenceView::find_iter`](crate::StringSequenceView::find_iter).
+pub struct FindIter<'a, 'n, N> {
+    tokens: Enumerate<StringSequenceIter<'a>>,
+    searcher: Searcher<'n, N>,
+    matched: usize,
+    /// Next position to yield for an empty needle, which matches at every position.
+    empty_at: Option<usize>,
+}
+
+impl<'a, 'n, N: AsRef<str>> FindIter<'a, 'n, N> {
+    pub(crate) fn new(tokens: StringSequenceIter<'a>, needle: &'n [N]) -> Self {
+        Self {
+            empty_at: needle.is_empty().then_some(0),
+            tokens: tokens.enumerate(),
+            searcher: Searcher::new(needle, false),
+            matched: 0,
+        }
+    }
+}
+
+impl<N: AsRef<str>> Iterator for FindIter<'_, '_, N> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(position) = self.empty_at {
+            self.empty_at = self.tokens.next().map(|_| position + 1);
+            return Some(position);
+        }
+
+        for (index, token) in self.tokens.by_ref() {
+            self.matched = self.searcher.step(self.matched, token);
+
+            if self.matched == self.searcher.len() {
+                self.matched = 0;
+                return Some(index + 1 - self.searcher.len());
+            }
+        }
+
+        None
+    }
+}
+
+impl<N: AsRef<str>> FusedIterator for FindIter<'_, '_, N> {}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                            SPLIT                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+impl SharedStringSequence {
+    /// Splits this sequence by occurrences of `sep`, in the same way as [`str::split`]. Pieces
+    /// share the buffer with `self`.
+    pub fn split_on<'a, 'n, N: AsRef<str>>(&'a self, sep: &'n [N]) -> Split<'a, 'n, N> {
+        self.splitn(usize::MAX, sep)
+    }
+
+    /// Same as [`Self::split_on`], but yields at most `n` pieces, where the last one holds the
+    /// rest of the sequence. See [`str::splitn`].
+    pub fn splitn<'a, 'n, N: AsRef<str>>(&'a self, n: usize, sep: &'n [N]) -> Split<'a, 'n, N> {
+        Split { seq: self, matches: self.find_iter(sep), start: 0, remaining: n }
+    }
+}
+
+/// Iterator over pieces of a [`SharedStringSequence`] split by a separator. See
+/// [`SharedStringSequence::split_on`].
+pub struct Split<'a, 'n, N> {
+    seq: &'a SharedStringSequence,
+    matches: FindIter<'a, 'n, N>,
+    start: usize,
+    /// Number of pieces left to yield, which is zero once the rest is yielded.
+    remaining: usize,
+}
+
+impl<N: AsRef<str>> Iterator for Split<'_, '_, N> {
+    type Item = SharedStringSequence;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+
+        let next = if self.remaining > 0 { self.matches.next() } else { None };
+        let Some(position) = next else {
+            self.remaining = 0;
+            return Some(self.seq.narrow(self.start..));
+        };
+
+        let piece = self.seq.narrow(self.start..position);
+        self.start = position + self.matches.searcher.len();
+        Some(piece)
+    }
+}
+
+impl<N: AsRef<str>> FusedIterator for Split<'_, '_, N> {}
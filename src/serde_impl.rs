@@ -4,15 +4,14 @@
 /*                                 BORROWER FOR SERIALIZATION                                 */
 /* ------------------------------------------------------------------------------------------ */
 
-struct Borrower<'a>(&'a [u8], &'a [Range<u32>]);
-
-use std::ops::Range;
+struct Borrower<'a>(&'a [u8], &'a [Offset]);
 
 use serde::{de::SeqAccess, ser::SerializeSeq, Deserializer, Serialize, Serializer};
 
 use crate::{
-    base_trait::StringSequenceView, mutable::MutableStringSequence, SharedStringSequence,
-    StringSequence,
+    base_trait::{Offset, RawView, StringSequenceView},
+    mutable::MutableStringSequence,
+    SharedStringSequence, StringSequence,
 };
 
 impl<'a> serde::Serialize for Borrower<'a> {
@@ -31,8 +30,8 @@ impl<'a> serde::Serialize for Borrower<'a> {
     }
 }
 
-impl<'a> crate::base_trait::StringSequenceView for Borrower<'a> {
-    fn inner(&self) -> (&[u8], &[Range<u32>]) {
+impl<'a> crate::base_trait::RawView for Borrower<'a> {
+    fn inner(&self) -> (&[u8], &[Offset]) {
         (self.0, self.1)
     }
 }
@@ -41,7 +40,7 @@ impl<'a> crate::base_trait::StringSequenceView for Borrower<'a> {
 
 macro_rules! gen_ser {
     ($type_name:path) => {
-        impl<'a> Serialize for $type_name {
+        impl Serialize for $type_name {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where
                 S: Serializer,
@@ -88,7 +87,7 @@ impl<'de> serde::de::Deserialize<'de> for MutableStringSequence {
                 }
 
                 while let Some(value) = seq.next_element::<&str>()? {
-                    self.0.push_back(value);
+                    self.0.try_push_back(value).map_err(serde::de::Error::custom)?;
                 }
 
                 Ok(())
@@ -1,4 +1,6 @@
-use crate::{base_trait::ToRange, MutableStringSequence, SharedStringSequence, StringSequence};
+use crate::{
+    MutableStringSequence, SharedStringSequence, StringSequence, StringSequenceView, ToRange,
+};
 
 #[test]
 fn basics() {
@@ -15,6 +17,28 @@ fn basics() {
     );
 }
 
+#[test]
+fn generic_view() {
+    fn joined(seq: &impl StringSequenceView) -> String {
+        seq.iter().collect::<Vec<_>>().join("/")
+    }
+
+    let tokens = ["usr", "local", "bin"];
+    assert_eq!(joined(&StringSequence::from_slice(&tokens)), "usr/local/bin");
+    assert_eq!(joined(&MutableStringSequence::from_slice(&tokens)), "usr/local/bin");
+
+    let shared = SharedStringSequence::from_slice(&tokens).subsequence(1..);
+    assert_eq!(joined(&shared), "local/bin");
+    assert_eq!(StringSequenceView::text(&shared), "localbin");
+    assert_eq!(StringSequenceView::len(&shared), 2);
+
+    // A needle longer than the sequence never matches, even if the overlap does.
+    assert!(StringSequenceView::starts_with(&shared, &["local"]));
+    assert!(!StringSequenceView::starts_with(&shared, &["local", "bin", "sh"]));
+    assert!(StringSequenceView::ends_with(&shared, &["bin"]));
+    assert!(!StringSequenceView::ends_with(&shared, &["usr", "local", "bin"]));
+}
+
 #[test]
 fn mutation() {
     let mut seq = MutableStringSequence::new();
@@ -45,12 +69,50 @@ fn mutation() {
     assert_eq!(seq.text(), "howdyworld");
 
     assert_eq!(seq.drain(0..0).count(), 0);
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = seq.drain(2..1).count();
+    assert_eq!(reversed, 0);
     assert!(seq.iter().eq(["howdy", "world"]));
 
     seq.extend(seq.clone().drain(..).chain(seq.clone().drain(..)));
     assert!(seq.iter().eq(["howdy", "world", "howdy", "world", "howdy", "world"]));
 }
 
+#[test]
+fn boundary_index() {
+    let mut seq = MutableStringSequence::from_slice(&["a", "bc", "", "def"]);
+
+    seq.remove(1);
+    assert!(seq.iter().eq(["a", "", "def"]));
+    assert_eq!(seq.text(), "adef");
+
+    seq.insert(3, "gh");
+    seq.insert(1, "");
+    assert!(seq.iter().eq(["a", "", "", "def", "gh"]));
+
+    seq.pop_back();
+    assert!(seq.iter().eq(["a", "", "", "def"]));
+    assert!(seq.drain(2..).eq(["", "def"]));
+    assert_eq!(seq.text(), "a");
+
+    seq.clear();
+    assert!(seq.is_empty());
+    assert_eq!(StringSequence::from(&seq).len(), 0);
+
+    let shared = SharedStringSequence::from_slice(&["a", "b", "c", "d", "e"]);
+    let sub = shared.subsequence(1..4);
+    assert!(sub.narrow(1..).iter().eq(["c", "d"]));
+    assert_eq!(sub.narrow(1..1).text(), "");
+    assert_eq!(sub.narrow(..).into_full_sequence(), shared);
+
+    // `subsequence` ranges over the whole buffer, even on a narrowed sequence.
+    assert!(sub.subsequence(1..).iter().eq(["b", "c", "d", "e"]));
+    assert_eq!(sub.subsequence(..), shared);
+    assert!(sub.narrow(1..).subsequence(..2).iter().eq(["a", "b"]));
+    assert!(StringSequence::from(&sub).iter().eq(["b", "c", "d"]));
+    assert!(MutableStringSequence::from(sub.slice_ref(1..)).iter().eq(["c", "d"]));
+}
+
 macro_rules! generate_view_test {
     ($func_name:ident, $type_name:ty) => {
         fn $func_name(view: $type_name, expected: &[&str]) {
@@ -129,6 +191,7 @@ fn view() {
 }
 
 #[test]
+#[cfg(not(all(feature = "offset-u16", not(feature = "offset-u64"))))]
 fn stability() {
     for _ in 0..5 {
         let var: Vec<_> = (0..5000)
@@ -137,9 +200,1160 @@ fn stability() {
 
         let var = Vec::from_iter(var.iter().map(|x| x.as_str()));
         let var = &var[..];
-        let view = MutableStringSequence::from_slice(&var);
+        let view = MutableStringSequence::from_slice(var);
+        test_view_seq(view.clone().into(), var);
+        test_view_share(view.clone().into(), var);
+        test_view_mut(view, var);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "offset-u16", not(feature = "offset-u64")))]
+fn stability() {
+    for _ in 0..5 {
+        // Each token takes at most 1020 bytes; keep the text within offset range.
+        let num_tokens = crate::Offset::MAX as usize / 1024;
+        let var: Vec<_> = (0..num_tokens)
+            .map(|_| String::from_iter((0..rand::random::<u8>()).map(|_| rand::random::<char>())))
+            .collect();
+
+        let var = Vec::from_iter(var.iter().map(|x| x.as_str()));
+        let var = &var[..];
+        let view = MutableStringSequence::from_slice(var);
         test_view_seq(view.clone().into(), var);
         test_view_share(view.clone().into(), var);
         test_view_mut(view, var);
     }
 }
+
+#[test]
+fn borrowed_lookup() {
+    use crate::StrSeq;
+    use std::collections::{BTreeMap, HashMap};
+    use std::ops::Bound;
+
+    let mut hash = HashMap::new();
+    let mut tree = BTreeMap::new();
+    for (value, key) in [["a", "b"], ["a", "c"], ["b", "a"]].iter().enumerate() {
+        hash.insert(SharedStringSequence::from_slice(key), value);
+        tree.insert(StringSequence::from_slice(key), value);
+    }
+
+    let parsed = MutableStringSequence::from_slice(&["x", "a", "c", "y"]);
+    let key: &StrSeq = &parsed.slice_ref(1..3);
+    assert_eq!(hash.get(key), Some(&1));
+    assert_eq!(tree.get(key), Some(&1));
+    assert_eq!(hash.get(&*StringSequence::from_slice(&["b", "a"])), Some(&2));
+    assert_eq!(tree.get(&*parsed), None);
+
+    let shared = SharedStringSequence::from(&parsed);
+    assert_eq!(&*shared.subsequence(1..3), key);
+    assert_eq!(key.to_owned(), StringSequence::from_slice(&["a", "c"]));
+    let range = (Bound::Unbounded, Bound::Excluded(key));
+    assert!(tree.range::<StrSeq, _>(range).map(|(_, v)| *v).eq([0]));
+}
+
+#[test]
+fn fallible_mutation() {
+    use crate::MutationError;
+
+    let mut seq = MutableStringSequence::from_slice(&["a", "b"]);
+    assert_eq!(seq.try_remove(2), Err(MutationError::OutOfBounds { index: 2, len: 2 }));
+    assert_eq!(seq.try_insert(3, "c"), Err(MutationError::OutOfBounds { index: 3, len: 2 }));
+    assert_eq!(seq.try_insert(2, "c"), Ok(()));
+    assert_eq!(seq.try_remove(0), Ok(()));
+    assert!(seq.iter().eq(["b", "c"]));
+
+    assert_eq!(seq.try_pop_back(), Ok(()));
+    assert_eq!(seq.try_pop_back(), Ok(()));
+    assert_eq!(seq.try_pop_back(), Err(MutationError::Empty));
+    assert!(std::panic::catch_unwind(move || seq.clone().pop_back()).is_err());
+}
+
+#[test]
+#[cfg(all(feature = "offset-u16", not(feature = "offset-u64")))]
+fn offset_overflow() {
+    use crate::CapacityError;
+
+    let long = "x".repeat(u16::MAX as usize - 1);
+    let mut seq = MutableStringSequence::from_slice(&[&long[..]]);
+    seq.push_back("y");
+    assert_eq!(seq.text().len(), u16::MAX as usize);
+
+    assert_eq!(seq.try_push_back("z"), Err(CapacityError::TextOverflow));
+    assert_eq!(seq.try_insert(0, "z"), Err(CapacityError::TextOverflow.into()));
+    assert_eq!(seq.try_extend(["", "", "z"]), Err(CapacityError::TextOverflow));
+    assert_eq!(seq.try_extend_from_slice(&["", "z"]), Err(CapacityError::TextOverflow));
+    assert_eq!(seq.len(), 2);
+    assert!(std::panic::catch_unwind(move || seq.clone().push_back("z")).is_err());
+
+    let mut seq = MutableStringSequence::new();
+    seq.extend((0..u16::MAX).map(|_| ""));
+    assert_eq!(seq.try_push_back(""), Err(CapacityError::TokenOverflow));
+    assert_eq!(seq.len(), u16::MAX as usize);
+}
+
+#[test]
+fn delimited() {
+    for text in ["", "/", "a", "usr/local/bin", "/root//ㅇㄴ🤣/", "a/bc/def/ghij/"] {
+        let expected: Vec<_> = text.split('/').collect();
+
+        let seq = StringSequence::parse_delimited(text, '/');
+        assert!(seq.iter().eq(expected.iter().copied()));
+        assert_eq!(seq.display_with('/').to_string(), text);
+
+        let shared = SharedStringSequence::parse_delimited(text, '/');
+        assert_eq!(shared, seq);
+        assert_eq!(shared.display_with("/").to_string(), text);
+
+        let mutable = MutableStringSequence::parse_delimited(text, '/');
+        assert_eq!(mutable, seq);
+        assert_eq!(format!("{}", mutable.display_with('/')), text);
+    }
+
+    let seq = StringSequence::parse_delimited("a::b::c", ':');
+    assert!(seq.iter().eq(["a", "", "b", "", "c"]));
+    assert_eq!(seq.slice_ref(2..).display_with(" > ").to_string(), "b >  > c");
+}
+
+#[test]
+fn delimited_codec() {
+    use crate::{delimited::DelimitedCodec, DelimitedError};
+
+    let plain = DelimitedCodec::default();
+    let quoted = DelimitedCodec::new(',', '\\').with_quote('"');
+    let alphabet = ["/", "\\", "\"", ",", "a", "ㅇ", "🤣", " "];
+
+    for _ in 0..2000 {
+        let tokens: Vec<String> = (0..rand::random::<usize>() % 5)
+            .map(|_| {
+                let len = rand::random::<usize>() % 5;
+                (0..len).map(|_| alphabet[rand::random::<usize>() % alphabet.len()]).collect()
+            })
+            .collect();
+
+        let seq = StringSequence::from_slice(&tokens);
+        for codec in [plain, quoted] {
+            let encoded = codec.encode(&seq);
+            assert_eq!(codec.display(&seq).to_string(), encoded);
+            assert_eq!(StringSequence::parse_with(&encoded, &codec).unwrap(), seq, "{encoded}");
+            assert_eq!(SharedStringSequence::parse_with(&encoded, &codec).unwrap(), seq);
+        }
+    }
+
+    assert_eq!(plain.encode(&MutableStringSequence::new()), "\\");
+    assert_eq!(plain.encode(&StringSequence::from_slice(&[""])), "");
+    assert_eq!(quoted.encode(&StringSequence::from_slice(&["a,b", "c\"d"])), r#""a,b",c\"d"#);
+
+    assert_eq!(plain.decode("a\\").unwrap_err(), DelimitedError::DanglingEscape { position: 1 });
+    assert_eq!(
+        plain.decode("a/\\b").unwrap_err(),
+        DelimitedError::InvalidEscape { position: 2, found: 'b' }
+    );
+    assert_eq!(
+        quoted.decode("a,\"b").unwrap_err(),
+        DelimitedError::UnterminatedQuote { position: 2 }
+    );
+    assert_eq!(
+        quoted.decode("\"a\"b").unwrap_err(),
+        DelimitedError::UnexpectedQuote { position: 2 }
+    );
+    assert_eq!(quoted.decode("a\"b").unwrap_err(), DelimitedError::UnexpectedQuote { position: 1 });
+
+    let mut seq = MutableStringSequence::from_slice(&["x"]);
+    assert!(plain.decode_into("a/b\\", &mut seq).is_err());
+    assert!(seq.iter().eq(["x"]));
+    plain.decode_into("a/b", &mut seq).unwrap();
+    assert!(seq.iter().eq(["x", "a", "b"]));
+}
+
+#[test]
+fn path_semantics() {
+    use crate::path::PathExt;
+
+    let shared = SharedStringSequence::parse_delimited("usr/lib/libfoo.so.1", '/');
+    assert_eq!(shared.file_name(), Some("libfoo.so.1"));
+    assert_eq!(shared.file_stem(), Some("libfoo.so"));
+    assert_eq!(shared.extension(), Some("1"));
+
+    let parent = shared.parent().unwrap();
+    assert!(parent.iter().eq(["usr", "lib"]));
+    assert_eq!(parent.parent().unwrap().parent().unwrap().len(), 0);
+    assert!(parent.parent().unwrap().parent().unwrap().parent().is_none());
+
+    let ancestors: Vec<_> = shared.subsequence(1..).ancestors().collect();
+    assert_eq!(ancestors.len(), 3);
+    assert!(ancestors[1].iter().eq(["lib"]));
+    assert!(ancestors[2].is_empty());
+
+    for (name, stem, ext) in [(".bashrc", Some(".bashrc"), None), ("a.", Some("a"), Some(""))] {
+        let seq = StringSequence::from_slice(&["x", name]);
+        assert_eq!((seq.file_stem(), seq.extension()), (stem, ext));
+    }
+
+    let dots = StringSequence::from_slice(&["a", ".."]);
+    assert_eq!((dots.file_name(), dots.file_stem(), dots.extension()), (None, None, None));
+    assert!(dots.with_file_name("b").iter().eq(["a", "..", "b"]));
+    assert!(shared.with_file_name("b").iter().eq(["usr", "lib", "b"]));
+    assert!(shared.with_extension("2").iter().eq(["usr", "lib", "libfoo.so.2"]));
+    assert!(shared.with_extension("").iter().eq(["usr", "lib", "libfoo.so"]));
+
+    let mut seq = MutableStringSequence::from_slice(&["x"]);
+    assert!(seq.set_extension("rs"));
+    assert!(seq.iter().eq(["x.rs"]));
+    assert!(!MutableStringSequence::new().set_extension("rs"));
+
+    for (input, expected) in [
+        ("a/./b/../c", "a/c"),
+        ("../a/../../b/.", "../../b"),
+        ("a/b/../..", ""),
+        ("./ㅇㄴ/🤣/../x", "ㅇㄴ/x"),
+        ("../..", "../.."),
+    ] {
+        let normalized = StringSequence::parse_delimited(input, '/').normalized();
+        let expected = match expected {
+            "" => MutableStringSequence::new(),
+            _ => MutableStringSequence::parse_delimited(expected, '/'),
+        };
+        assert_eq!(normalized, expected, "{input}");
+        assert_eq!(normalized.text().len(), expected.text().len());
+    }
+}
+
+#[test]
+fn path_relation() {
+    use crate::path::PathExt;
+
+    let seq = SharedStringSequence::parse_delimited("srv/www/static/app.js", '/');
+    let prefix = MutableStringSequence::parse_delimited("srv/www", '/');
+
+    let stripped = seq.strip_prefix(&prefix).unwrap();
+    assert!(stripped.iter().eq(["static", "app.js"]));
+    assert_eq!(stripped.strip_prefix(&seq.subsequence(2..3)).unwrap().text(), "app.js");
+    assert!(stripped.strip_prefix(&prefix).is_none());
+    assert!(stripped.strip_prefix(&seq).is_none());
+    assert_eq!(seq.strip_prefix(&seq).unwrap().len(), 0);
+
+    let stripped = seq.strip_suffix(&StringSequence::from_slice(&["static", "app.js"])).unwrap();
+    assert_eq!(stripped, prefix);
+    assert!(stripped.strip_suffix(&["static"][..].iter().collect::<StringSequence>()).is_none());
+    assert_eq!(seq.strip_suffix(&MutableStringSequence::new()).unwrap(), seq);
+
+    let relative = |a: &str, b: &str| {
+        let a = StringSequence::parse_delimited(a, '/');
+        let b = StringSequence::parse_delimited(b, '/');
+        a.relative_to(&b).map(|x| x.display_with('/').to_string())
+    };
+    assert_eq!(relative("a/x/y", "a/b/c").as_deref(), Some("../../x/y"));
+    assert_eq!(relative("a/b", "a/b/c").as_deref(), Some(".."));
+    assert_eq!(relative("a/b/c", "a/b").as_deref(), Some("c"));
+    assert_eq!(relative("a/b", "a/b").as_deref(), Some(""));
+    assert_eq!(relative("x", "../a"), None);
+    assert_eq!(relative("../x", "../a").as_deref(), Some("../x"));
+}
+
+#[test]
+fn std_path() {
+    use crate::path::PathExt;
+    use std::path::{Path, PathBuf};
+
+    let path = Path::new("/usr/./lib//../share/doc/");
+    let seq = SharedStringSequence::from_path(path).unwrap();
+    let root = std::path::MAIN_SEPARATOR_STR;
+    assert!(seq.iter().eq([root, "usr", "lib", "..", "share", "doc"]));
+    assert_eq!(seq.to_path_buf(), PathBuf::from_iter([root, "usr", "lib", "..", "share", "doc"]));
+    assert_eq!(
+        seq.to_path_buf().components().collect::<Vec<_>>(),
+        path.components().collect::<Vec<_>>()
+    );
+
+    let normalized = seq.normalized();
+    assert!(normalized.iter().eq([root, "usr", "share", "doc"]));
+    assert_eq!(normalized.to_path_buf(), Path::new(root).join("usr/share/doc"));
+
+    let escaped = StringSequence::from_slice(&[root, "..", "..", "a"]).normalized();
+    assert!(escaped.iter().eq([root, "a"]));
+
+    let relative = StringSequence::from_path("./a/b").unwrap();
+    assert!(relative.iter().eq([".", "a", "b"]));
+    assert_eq!(relative.to_path_buf(), Path::new("./a/b"));
+    assert_eq!(MutableStringSequence::from_path("").unwrap().len(), 0);
+    assert_eq!(MutableStringSequence::new().to_path_buf(), PathBuf::new());
+}
+
+#[test]
+fn trie() {
+    use crate::trie::SequenceTrie;
+
+    let key = |x: &str| StringSequence::parse_delimited(x, '/');
+    let mut trie = SequenceTrie::new();
+
+    assert_eq!(trie.insert(&key("a/b"), 1), None);
+    assert_eq!(trie.insert(&key("a/b/c"), 2), None);
+    assert_eq!(trie.insert(&key("a/x"), 3), None);
+    assert_eq!(trie.insert(&key("a/b"), 4), Some(1));
+    assert_eq!(trie.len(), 3);
+
+    assert_eq!(trie.get(&key("a/b")), Some(&4));
+    assert_eq!(trie.get(&key("a")), None);
+    assert_eq!(trie.get(&MutableStringSequence::from_slice(&["a", "x"])), Some(&3));
+
+    *trie.entry(&key("a/b")).and_modify(|x| *x += 10).or_insert(0) += 1;
+    *trie.entry(&key("z")).or_default() += 5;
+    assert_eq!(trie.get(&key("a/b")), Some(&15));
+    assert_eq!(trie.get(&key("z")), Some(&5));
+    assert_eq!(trie.len(), 4);
+
+    let (prefix, value) = trie.longest_prefix_match(&key("a/b/c/d")).unwrap();
+    assert!(prefix.iter().eq(["a", "b", "c"]));
+    assert_eq!(*value, 2);
+    assert!(trie.longest_prefix_match(&key("a/y")).is_none());
+
+    let keys = |iter: crate::trie::Iter<'_, i32>| {
+        iter.map(|(k, v)| (k.display_with('/').to_string(), *v)).collect::<Vec<_>>()
+    };
+    let all = keys(trie.iter());
+    assert_eq!(all, [("a/b".into(), 15), ("a/b/c".into(), 2), ("a/x".into(), 3), ("z".into(), 5)]);
+    assert_eq!(keys(trie.iter_prefix(&key("a/b"))), [("a/b".into(), 15), ("a/b/c".into(), 2)]);
+    assert!(trie.iter_prefix(&key("q")).next().is_none());
+
+    assert_eq!(trie.remove(&key("a/b")), Some(15));
+    assert_eq!(trie.get(&key("a/b/c")), Some(&2));
+    assert_eq!(trie.len(), 3);
+
+    let removed = trie.remove_subtree(&key("a"));
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed.get(&key("b/c")), Some(&2));
+    assert_eq!(trie.len(), 1);
+    assert_eq!(keys(trie.iter()), [("z".into(), 5)]);
+    assert!(trie.remove_subtree(&key("a")).is_empty());
+}
+
+#[test]
+fn pattern() {
+    use crate::{pattern::SequencePattern, PatternError};
+
+    let seq = |x: &str| StringSequence::parse_delimited(x, '/');
+    let pattern = |x: &str| SequencePattern::parse(x, '/').unwrap();
+
+    assert!(pattern("a/*/c").matches(&seq("a/b/c")));
+    assert!(!pattern("a/*/c").matches(&seq("a/c")));
+    assert!(pattern("**").matches(&MutableStringSequence::new()));
+    assert!(pattern("a/**").matches(&seq("a")));
+    assert!(pattern("**/*.rs").matches(&seq("src/x/lib.rs")));
+    assert!(!pattern("**/*.rs").matches(&seq("src/x/lib.rsx")));
+    assert!(pattern("file?.[ch]").matches(&seq("file1.c")));
+    assert!(!pattern("file?.[ch]").matches(&seq("file.c")));
+    assert!(pattern("[!0-9]*").matches(&seq("x12")));
+    assert!(!pattern("[!0-9]*").matches(&seq("1x")));
+    assert!(pattern("[]a-]").matches(&seq("]")) && pattern("[]a-]").matches(&seq("-")));
+    assert!(pattern(r"\*\?").matches(&seq("*?")) && !pattern(r"\*\?").matches(&seq("ab")));
+    assert!(pattern("*a*b*").matches(&seq("xxaybzb")));
+
+    let path = seq("a/x/b/y/z/b/c");
+    let captures = pattern("a/**/b/**/c").captures(&path).unwrap();
+    assert_eq!(captures.len(), 2);
+    assert!(captures[0].iter().eq(["x"]));
+    assert!(captures[1].iter().eq(["y", "z", "b"]));
+
+    let single = seq("b");
+    let captures = pattern("**/b/**").captures(&single).unwrap();
+    assert!(captures.iter().all(|x| x.is_empty()));
+    assert!(pattern("a/**/d").captures(&path).is_none());
+
+    assert_eq!(
+        SequencePattern::parse("a/b[c", '/').unwrap_err(),
+        PatternError::UnterminatedClass { token: 1, position: 1 }
+    );
+    assert_eq!(
+        SequencePattern::new(["x\\"]).unwrap_err(),
+        PatternError::DanglingEscape { token: 0, position: 1 }
+    );
+    assert_eq!(
+        SequencePattern::new(["[z-a]"]).unwrap_err(),
+        PatternError::InvalidRange { token: 0, position: 1 }
+    );
+}
+
+#[test]
+fn topic() {
+    use crate::topic::{TopicFilter, TopicRouter};
+    use crate::TopicFilterError;
+
+    let topic = |x: &str| SharedStringSequence::parse_delimited(x, '/');
+    let filter = |x: &str| TopicFilter::parse(x, '/').unwrap();
+
+    assert!(filter("a/+/c").matches(&topic("a/b/c")));
+    assert!(!filter("a/+/c").matches(&topic("a/c")));
+    assert!(filter("a/#").matches(&topic("a")));
+    assert!(filter("a/#").matches(&topic("a/b/c")));
+    assert!(!filter("a/#").matches(&topic("b")));
+    assert!(filter("#").matches(&topic("x/y")));
+    assert!(!filter("#").matches(&topic("$SYS/x")));
+    assert!(!filter("+/x").matches(&topic("$SYS/x")));
+    assert!(filter("$SYS/#").matches(&topic("$SYS/x")));
+    assert!(filter("a/+").matches(&topic("a/")));
+    assert!(!filter("a/b").has_wildcard() && filter("+").has_wildcard());
+
+    assert_eq!(
+        TopicFilter::parse("a/#/b", '/').unwrap_err(),
+        TopicFilterError::MisplacedTail { token: 1 }
+    );
+    assert_eq!(
+        TopicFilter::parse("a/b+", '/').unwrap_err(),
+        TopicFilterError::InvalidWildcard { token: 1 }
+    );
+    assert_eq!(TopicFilter::new(&MutableStringSequence::new()), Err(TopicFilterError::Empty));
+
+    let filters = ["#", "+/b", "a/b", "a/+", "a/#", "a/b/#", "+/+/c", "$SYS/#", "x"];
+    let mut router = TopicRouter::new();
+    for (index, x) in filters.iter().enumerate() {
+        assert_eq!(router.insert(&filter(x), index), None);
+    }
+    assert_eq!(router.len(), filters.len());
+    assert_eq!(router.insert(&filter("x"), 100), Some(8));
+    assert_eq!(router.get(&filter("a/+")), Some(&3));
+
+    // Every published topic should be routed to the same filters as a linear scan.
+    for published in ["a/b", "a", "a/b/c", "q/b", "$SYS/load", "x", "a/z/c", "", "a/b/c/d"] {
+        let published = topic(published);
+        let mut routed = router.route(&published).into_iter().copied().collect::<Vec<_>>();
+        routed.sort();
+
+        let scanned = (0..filters.len())
+            .filter(|&x| filter(filters[x]).matches(&published))
+            .map(|x| if x == 8 { 100 } else { x })
+            .collect::<Vec<_>>();
+        assert_eq!(routed, scanned, "{published:?}");
+    }
+
+    assert_eq!(router.remove(&filter("+/b")), Some(1));
+    assert_eq!(router.remove(&filter("+/b")), None);
+    assert_eq!(router.remove(&filter("+/+/c")), Some(6));
+    assert_eq!(router.len(), filters.len() - 2);
+    assert!(!router.route(&topic("q/b")).contains(&&1));
+}
+
+#[test]
+fn route_table() {
+    use crate::route::RouteTable;
+    use crate::RouteError;
+
+    let seq = |x: &str| StringSequence::parse_delimited(x, '/');
+    let mut table = RouteTable::new();
+
+    for (index, route) in
+        ["users/:id", "users/me", "users/:id/posts/*rest", "users/:id/posts", "*all", "a/:x/c"]
+            .into_iter()
+            .enumerate()
+    {
+        table.insert(&seq(route), index).unwrap();
+    }
+    table.insert(&seq("a/b/d"), 6).unwrap();
+    assert_eq!(table.len(), 7);
+
+    let resolve = |path: &str| {
+        let path = seq(path);
+        table.resolve(&path).map(|(value, params)| {
+            let params = params
+                .iter()
+                .map(|(name, x)| format!("{name}={}", x.display_with('/')))
+                .collect::<Vec<_>>();
+            (*value, params)
+        })
+    };
+
+    assert_eq!(resolve("users/me"), Some((1, vec![])));
+    assert_eq!(resolve("users/42"), Some((0, vec!["id=42".into()])));
+    assert_eq!(resolve("users/42/posts"), Some((3, vec!["id=42".into()])));
+    assert_eq!(
+        resolve("users/42/posts/7/edit"),
+        Some((2, vec!["id=42".into(), "rest=7/edit".into()]))
+    );
+    assert_eq!(resolve("users/"), Some((4, vec!["all=users/".into()])));
+    assert_eq!(resolve("other"), Some((4, vec!["all=other".into()])));
+
+    // Static `b` fails deeper, so the matcher backtracks into `:x`.
+    assert_eq!(resolve("a/b/c"), Some((5, vec!["x=b".into()])));
+    assert_eq!(resolve("a/b/d"), Some((6, vec![])));
+
+    let path = seq("users/42/posts/7");
+    let (_, params) = table.resolve(&path).unwrap();
+    assert_eq!(params.get("id"), Some("42"));
+    assert_eq!(params.get("rest"), None);
+    assert!(params.get_seq("rest").unwrap().iter().eq(["7"]));
+    assert_eq!(params.len(), 2);
+
+    assert_eq!(table.insert(&seq("users/:name"), 0), Err(RouteError::Conflict));
+
+    // `:name` and `*name` after the same tokens would both match a single token.
+    assert_eq!(table.insert(&seq("users/*rest"), 0), Err(RouteError::Conflict));
+    assert_eq!(table.insert(&seq("users/:id/posts/:post"), 0), Err(RouteError::Conflict));
+    assert_eq!(table.insert(&seq(":x/y"), 0), Err(RouteError::Conflict));
+    assert_eq!(table.insert(&seq("*x/a"), 0), Err(RouteError::MisplacedCatchAll { token: 0 }));
+    assert_eq!(table.insert(&seq("x/:"), 0), Err(RouteError::EmptyName { token: 1 }));
+    assert_eq!(table.insert(&seq(":a/:a"), 0), Err(RouteError::DuplicateName { token: 1 }));
+    assert_eq!(table.len(), 7);
+
+    assert_eq!(table.remove(&seq("*other")), Some(4));
+    assert_eq!(table.remove(&seq("*other")), None);
+    assert!(table.resolve(&seq("other")).is_none());
+    assert_eq!(table.len(), 6);
+
+    table.insert(&seq(":x/y"), 7).unwrap();
+    assert_eq!(table.resolve(&seq("other/y")).map(|(x, _)| *x), Some(7));
+}
+
+#[test]
+fn search() {
+    let seq = StringSequence::from_slice(&["a", "b", "a", "b", "a", "c", "a", "b"]);
+    assert_eq!(seq.find(&["a", "b"]), Some(0));
+    assert_eq!(seq.rfind(&["a", "b"]), Some(6));
+    assert_eq!(seq.find(&["a", "b", "a", "c"]), Some(2));
+    assert_eq!(seq.find(&["c", "b"]), None);
+    assert_eq!(seq.rfind(&["c", "b"]), None);
+    assert!(seq.find_iter(&["a", "b", "a"]).eq([0]));
+    assert!(seq.find_iter(&["a"]).eq([0, 2, 4, 6]));
+    assert!(seq.find_iter(&[] as &[&str]).eq(0..=seq.len()));
+    assert_eq!(seq.rfind(&[] as &[&str]), Some(seq.len()));
+    assert!(seq.contains(&["c", "a"]) && !seq.contains(&["b", "b"]));
+    assert!(seq.contains(&["b", "a", "c", "a", "b"]) && !seq.contains(&["b", "a", "c", "a", "c"]));
+
+    // Compare against naive search over small alphabets, where partial matches are frequent.
+    let tokens = ["x", "y", "xy", ""];
+
+    for _ in 0..500 {
+        let haystack: Vec<_> = (0..rand::random::<usize>() % 30)
+            .map(|_| tokens[rand::random::<usize>() % 4])
+            .collect();
+        let needle: Vec<_> = (0..1 + rand::random::<usize>() % 6)
+            .map(|_| tokens[rand::random::<usize>() % 4])
+            .collect();
+        let seq = MutableStringSequence::from_slice(&haystack);
+
+        let naive: Vec<_> = (0..=haystack.len().saturating_sub(needle.len()))
+            .filter(|&i| haystack[i..].starts_with(&needle))
+            .collect();
+
+        let mut non_overlapping = Vec::<usize>::new();
+        for &i in &naive {
+            if non_overlapping.last().is_none_or(|&x| x + needle.len() <= i) {
+                non_overlapping.push(i);
+            }
+        }
+
+        assert_eq!(seq.find_iter(&needle).collect::<Vec<_>>(), non_overlapping);
+        assert_eq!(seq.find(&needle), naive.first().copied());
+        assert_eq!(seq.rfind(&needle), naive.last().copied());
+        assert_eq!(seq.contains(&needle), !naive.is_empty());
+    }
+}
+
+#[test]
+fn split_replace() {
+    let seq = |x: &str| SharedStringSequence::parse_delimited(x, '/');
+    let pieces = |iter: crate::search::Split<'_, '_, &str>| {
+        iter.map(|x| x.display_with('/').to_string()).collect::<Vec<_>>()
+    };
+
+    let path = seq("a/-/b/c/-/-/d");
+    assert_eq!(pieces(path.split_on(&["-"])), ["a", "b/c", "", "d"]);
+    assert_eq!(pieces(path.split_on(&["-", "-"])), ["a/-/b/c", "d"]);
+    assert_eq!(pieces(path.split_on(&["x"])), ["a/-/b/c/-/-/d"]);
+    assert_eq!(pieces(path.splitn(2, &["-"])), ["a", "b/c/-/-/d"]);
+    assert_eq!(pieces(path.splitn(1, &["-"])), ["a/-/b/c/-/-/d"]);
+    assert!(path.splitn(0, &["-"]).next().is_none());
+    assert_eq!(pieces(seq("-/a/-").split_on(&["-"])), ["", "a", ""]);
+    assert_eq!(pieces(seq("a/b").split_on(&[])), ["", "a", "b", ""]);
+
+    // Pieces share the buffer with the original.
+    let piece = path.split_on(&["-"]).nth(1).unwrap();
+    assert!(piece.into_full_sequence() == path);
+
+    let (head, tail) = path.split_at(2);
+    assert!(head.iter().eq(["a", "-"]) && tail.len() == 5);
+    let (head, tail) = path.split_at(path.len());
+    assert!(head == path && tail.is_empty());
+
+    let replaced = path.replace(&["-"], &["x", "y"]);
+    assert_eq!(replaced.display_with('/').to_string(), "a/x/y/b/c/x/y/x/y/d");
+    assert_eq!(
+        path.replace(&["-", "-"], &[] as &[&str]).display_with('/').to_string(),
+        "a/-/b/c/d"
+    );
+    assert_eq!(
+        seq("a/b").replace(&[] as &[&str], &["_"]).display_with('/').to_string(),
+        "_/a/_/b/_"
+    );
+}
+
+#[test]
+fn diff() {
+    use crate::diff::{diff, levenshtein, Edit, EditKind};
+    use crate::PatchError;
+
+    let seq = |x: &str| StringSequence::parse_delimited(x, '/');
+
+    let (old, new) = (seq("a/b/c/d"), seq("a/x/c/d/e"));
+    let script = diff(&old, &new);
+    assert_eq!(
+        script,
+        [
+            Edit { kind: EditKind::Keep, old: 0..1, new: 0..1 },
+            Edit { kind: EditKind::Replace, old: 1..2, new: 1..2 },
+            Edit { kind: EditKind::Keep, old: 2..4, new: 2..4 },
+            Edit { kind: EditKind::Insert, old: 4..4, new: 4..5 },
+        ]
+    );
+    assert!(diff(&MutableStringSequence::new(), &MutableStringSequence::new()).is_empty());
+
+    let mut patched = MutableStringSequence::from(&old);
+    patched.apply_patch(&script, &new);
+    assert_eq!(patched, new);
+
+    let mut invalid = MutableStringSequence::from(&old);
+    let gap = [Edit { kind: EditKind::Delete, old: 1..2, new: 0..0 }];
+    assert_eq!(invalid.try_apply_patch(&gap, &new), Err(PatchError::OldRange { step: 0 }));
+    let beyond = [Edit { kind: EditKind::Insert, old: 0..0, new: 4..9 }];
+    assert_eq!(invalid.try_apply_patch(&beyond, &new), Err(PatchError::NewRange { step: 0 }));
+    let insert = [
+        Edit { kind: EditKind::Keep, old: 0..1, new: 0..1 },
+        Edit { kind: EditKind::Insert, old: 1..2, new: 1..2 },
+    ];
+    assert_eq!(invalid.try_apply_patch(&insert, &new), Err(PatchError::KindMismatch { step: 1 }));
+    assert_eq!(invalid, old);
+
+    assert_eq!(levenshtein(&seq("a/b/c"), &seq("a/c/d")), 2);
+    assert_eq!(levenshtein(&seq("a/b"), &seq("x/y/z")), 3);
+    assert_eq!(levenshtein(&MutableStringSequence::new(), &seq("a/b")), 2);
+
+    // Every script should reproduce the new sequence, with the least insertions and deletions.
+    let tokens = ["a", "b", "c"];
+    for _ in 0..300 {
+        let old = MutableStringSequence::from_iter(
+            (0..rand::random::<usize>() % 12).map(|_| tokens[rand::random::<usize>() % 3]),
+        );
+        let new = MutableStringSequence::from_iter(
+            (0..rand::random::<usize>() % 12).map(|_| tokens[rand::random::<usize>() % 3]),
+        );
+
+        let script = diff(&old, &new);
+        let mut patched = old.clone();
+        patched.apply_patch(&script, &new);
+        assert_eq!(patched, new);
+
+        let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for (i, x) in old.iter().enumerate() {
+            for (j, y) in new.iter().enumerate() {
+                lcs[i + 1][j + 1] =
+                    if x == y { lcs[i][j] + 1 } else { lcs[i][j + 1].max(lcs[i + 1][j]) };
+            }
+        }
+
+        let num_changed: usize = script
+            .iter()
+            .filter(|x| x.kind != EditKind::Keep)
+            .map(|x| x.old.len() + x.new.len())
+            .sum();
+        assert_eq!(num_changed, old.len() + new.len() - 2 * lcs[old.len()][new.len()]);
+        assert!(levenshtein(&old, &new) <= num_changed);
+    }
+}
+
+#[test]
+fn common_affix() {
+    use crate::{StrSeq, StringSequenceRef};
+
+    let seq = |x: &str| SharedStringSequence::parse_delimited(x, '/');
+
+    let a = seq("usr/local/lib/x.so");
+    assert_eq!(a.common_prefix_len(&StringSequence::parse_delimited("usr/local/bin", '/')), 2);
+    assert_eq!(a.common_suffix_len(&MutableStringSequence::from_slice(&["lib", "x.so"])), 2);
+    assert_eq!(a.common_prefix_len(&MutableStringSequence::new()), 0);
+
+    let paths = [a.clone(), seq("usr/local/bin/y"), seq("usr/local/lib")];
+    let prefix = SharedStringSequence::common_prefix(&paths).unwrap();
+    assert!(prefix.iter().eq(["usr", "local"]));
+    assert!(prefix.into_full_sequence() == a);
+
+    let files = vec![seq("a/x/lib.rs"), seq("b/x/lib.rs"), seq("x/lib.rs")];
+    let suffix = SharedStringSequence::common_suffix(files).unwrap();
+    assert!(suffix.iter().eq(["x", "lib.rs"]));
+
+    assert!(SharedStringSequence::common_prefix([seq("a"), seq("b")]).unwrap().is_empty());
+    assert!(SharedStringSequence::common_suffix([a.clone()]).unwrap() == a);
+    assert!(SharedStringSequence::common_prefix(Vec::<SharedStringSequence>::new()).is_none());
+
+    // Any other sequence type, borrowed from the first one.
+    let owned = ["usr/local/lib", "usr/local/bin/sh", "usr/lib"]
+        .map(|x| StringSequence::parse_delimited(x, '/'));
+    let prefix = StringSequenceRef::common_prefix(&owned).unwrap();
+    assert!(prefix.iter().eq(["usr"]));
+    assert_eq!(prefix.text().as_ptr(), owned[0].text().as_ptr());
+
+    let mutable = [MutableStringSequence::from_slice(&["a", "x", "y"])];
+    assert!(StringSequenceRef::common_suffix(&mutable).unwrap().iter().eq(["a", "x", "y"]));
+
+    let (x, y) = (seq("a/x/lib.rs"), StringSequence::parse_delimited("x/lib.rs", '/'));
+    let borrowed: [&StrSeq; 2] = [&x, &y];
+    assert!(StringSequenceRef::common_prefix(borrowed).unwrap().is_empty());
+    assert!(StringSequenceRef::common_suffix(borrowed).unwrap().iter().eq(["x", "lib.rs"]));
+    assert!(StringSequenceRef::common_prefix(Vec::<&StrSeq>::new()).is_none());
+}
+
+#[test]
+fn interner() {
+    use crate::interner::{InternerStats, SequenceInterner};
+
+    let interner = SequenceInterner::new();
+    let a = interner.intern(&StringSequence::parse_delimited("a/b", '/'));
+    let b = interner.intern(&MutableStringSequence::from_slice(&["a", "b"]));
+    let c = interner.intern(&StringSequence::parse_delimited("a/c", '/'));
+    assert!(SharedStringSequence::ptr_eq(&a, &b));
+    assert!(!SharedStringSequence::ptr_eq(&a, &c));
+    assert!(!SharedStringSequence::ptr_eq(&a, &SharedStringSequence::from(&a)));
+
+    // Subsequence is copied, while a full sequence keeps its buffer.
+    let long = SharedStringSequence::parse_delimited("x/y/z", '/');
+    let sub = interner.intern_shared(long.subsequence(1..));
+    assert!(!SharedStringSequence::ptr_eq(&sub.clone().into_full_sequence(), &long));
+    let full = interner.intern_shared(long.clone());
+    assert!(SharedStringSequence::ptr_eq(&full, &long));
+
+    let pinned = interner.intern_pinned(&StringSequence::parse_delimited("p", '/'));
+    assert_eq!(interner.stats(), InternerStats { entries: 5, hits: 1, misses: 5, purged: 0 });
+
+    drop((pinned, c, sub));
+    assert_eq!(interner.purge(), 2);
+    assert!(interner.get(&StringSequence::parse_delimited("p", '/')).is_some());
+    assert!(interner.get(&StringSequence::parse_delimited("a/c", '/')).is_none());
+    assert!(SharedStringSequence::ptr_eq(&interner.get(&a).unwrap(), &b));
+
+    drop((a, b, full, long));
+    assert_eq!(interner.purge(), 2);
+    assert_eq!(interner.stats().purged, 4);
+    assert_eq!(interner.len(), 1);
+
+    // Unused entries are purged automatically as the interner grows.
+    for i in 0..1000 {
+        interner.intern(&StringSequence::from_iter([i.to_string()]));
+    }
+    assert!(interner.len() < 200);
+
+    // An entry dies with its last user, even before it's swept.
+    let dead = interner.intern(&StringSequence::parse_delimited("d", '/'));
+    assert!(interner.get(&dead).is_some());
+    drop(dead);
+    assert!(interner.get(&StringSequence::parse_delimited("d", '/')).is_none());
+
+    let global = SequenceInterner::global().intern(&StringSequence::parse_delimited("g", '/'));
+    assert!(SharedStringSequence::ptr_eq(
+        &global,
+        &SequenceInterner::global().intern(&MutableStringSequence::from_slice(&["g"]))
+    ));
+}
+
+#[test]
+fn fast_paths() {
+    use std::hash::{BuildHasher, RandomState};
+
+    let shared = SharedStringSequence::parse_delimited("a/b/c/d", '/');
+    let clone = shared.clone();
+    let copy = SharedStringSequence::from(&shared);
+    assert!(shared == clone && shared == copy);
+    assert_eq!(shared.cmp(&clone), std::cmp::Ordering::Equal);
+    assert!(shared.subsequence(1..) != shared.subsequence(..3));
+    assert!(shared.subsequence(1..) > shared.subsequence(..3));
+    assert!(shared.subsequence(..0) == MutableStringSequence::new());
+
+    // Hashes should agree between types and between full and narrowed views.
+    let state = RandomState::new();
+    let sub = shared.subsequence(1..3);
+    let hashes = [
+        state.hash_one(&sub),
+        state.hash_one(SharedStringSequence::from(&sub)),
+        state.hash_one(MutableStringSequence::from_slice(&["b", "c"])),
+        state.hash_one(StringSequence::from_slice(&["b", "c"])),
+        state.hash_one(&*sub.slice_ref(..)),
+        state.hash_one(sub.subsequence(..).into_full_sequence().subsequence(1..3)),
+    ];
+    assert!(hashes.iter().all(|x| *x == hashes[0]));
+    assert_eq!(state.hash_one(&shared), state.hash_one(&copy));
+    assert_ne!(
+        state.hash_one(StringSequence::from_slice(&["ab", "c"])),
+        state.hash_one(StringSequence::from_slice(&["a", "bc"]))
+    );
+}
+
+#[cfg(feature = "cached-hash")]
+#[test]
+fn cached_hash() {
+    use crate::StrSeq;
+    use std::hash::{BuildHasher, RandomState};
+
+    // A borrowed view forwards the fingerprint cached by its owner.
+    let state = RandomState::new();
+    let shared = SharedStringSequence::parse_delimited("a/b/c", '/');
+    let borrowed: &StrSeq = &shared;
+    assert_eq!(state.hash_one(borrowed), state.hash_one(&shared));
+
+    let sub = shared.narrow(1..);
+    let borrowed: &StrSeq = &sub;
+    assert_eq!(state.hash_one(borrowed), state.hash_one(&sub));
+    assert_eq!(state.hash_one(borrowed), state.hash_one(StringSequence::from_slice(&["b", "c"])));
+}
+
+#[test]
+fn memcomparable() {
+    use crate::memcomparable::MemcomparableExt;
+    use crate::DecodeError;
+
+    let tokens = ["", "a", "ab", "a\0", "\0", "b", "\u{ff}", "a\0b"];
+    let random_seq = || {
+        StringSequence::from_iter(
+            (0..rand::random::<usize>() % 4)
+                .map(|_| tokens[rand::random::<usize>() % tokens.len()]),
+        )
+    };
+
+    let mut seqs: Vec<_> = (0..300).map(|_| random_seq()).collect();
+    seqs.sort();
+
+    for pair in seqs.windows(2) {
+        let (a, b) = (pair[0].encode_memcomparable(), pair[1].encode_memcomparable());
+        assert_eq!(a.cmp(&b), pair[0].cmp(&pair[1]), "{:?}", pair);
+    }
+
+    for seq in &seqs {
+        let encoded = seq.encode_memcomparable();
+        assert_eq!(&StringSequence::decode_memcomparable(&encoded).unwrap(), seq);
+
+        let mut written = Vec::new();
+        seq.write_memcomparable(&mut written).unwrap();
+        assert_eq!(written, encoded);
+
+        // Keys in the range are exactly ones which start with the prefix.
+        let Some(end) = seq.memcomparable_upper_bound() else { continue };
+        for other in &seqs {
+            let key = other.encode_memcomparable();
+            let in_range = encoded <= key && key < end;
+            assert_eq!(in_range, other.iter().take(seq.len()).eq(seq.iter()), "{seq:?} {other:?}");
+        }
+    }
+
+    assert!(MutableStringSequence::new().memcomparable_upper_bound().is_none());
+    assert!(StringSequence::decode_memcomparable(&[]).unwrap().is_empty());
+    assert_eq!(
+        StringSequence::decode_memcomparable(b"a\0"),
+        Err(DecodeError::UnexpectedEnd { position: 2 })
+    );
+    assert_eq!(
+        StringSequence::decode_memcomparable(b"a\0\x01b"),
+        Err(DecodeError::UnexpectedEnd { position: 4 })
+    );
+    assert_eq!(
+        StringSequence::decode_memcomparable(b"a\0\x02"),
+        Err(DecodeError::InvalidByte { position: 2, found: 2 })
+    );
+    assert_eq!(
+        StringSequence::decode_memcomparable(b"a\0\x01\xc3\0\x01"),
+        Err(DecodeError::InvalidUtf8 { position: 3 })
+    );
+}
+
+#[test]
+fn binary() {
+    use crate::binary::BinaryExt;
+    use crate::DecodeError;
+
+    let tokens = ["", "a", "bc", "\u{ff}", "x".repeat(200).leak()];
+    let random_seq = || {
+        StringSequence::from_iter(
+            (0..rand::random::<usize>() % 6)
+                .map(|_| tokens[rand::random::<usize>() % tokens.len()]),
+        )
+    };
+
+    let seqs: Vec<_> = (0..100).map(|_| random_seq()).collect();
+    let mut stream = Vec::new();
+
+    for seq in &seqs {
+        let bytes = seq.to_bytes();
+        assert_eq!(&StringSequence::from_bytes(&bytes).unwrap(), seq);
+        seq.write_to(&mut stream).unwrap();
+    }
+
+    // Sequences are read back-to-back from a single reader.
+    let mut reader = &stream[..];
+    for seq in &seqs {
+        assert_eq!(&StringSequence::read_from(&mut reader).unwrap(), seq);
+    }
+    assert!(reader.is_empty());
+
+    let err = StringSequence::read_from(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    // A small buffer is refilled in the middle of a sequence, and keeps the rest for the next.
+    let mut reader = std::io::BufReader::with_capacity(3, &stream[..]);
+    for seq in &seqs {
+        assert_eq!(&StringSequence::read_from(&mut reader).unwrap(), seq);
+    }
+
+    assert_eq!(StringSequence::from_bytes(b"\x01\x00").unwrap().len(), 0);
+    assert_eq!(
+        StringSequence::from_bytes(b"\x02\x00"),
+        Err(DecodeError::UnsupportedVersion { version: 2 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\x00\x00"),
+        Err(DecodeError::TrailingBytes { position: 2 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\x02\x01\x02ab"),
+        Err(DecodeError::UnexpectedEnd { position: 6 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\x02\x01\x01a\xc3"),
+        Err(DecodeError::InvalidUtf8 { position: 5 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02"),
+        Err(DecodeError::InvalidByte { position: 10, found: 2 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\x81\x00\x00"),
+        Err(DecodeError::InvalidByte { position: 2, found: 0 })
+    );
+    assert_eq!(
+        StringSequence::from_bytes(b"\x01\x01\x80\x00"),
+        Err(DecodeError::InvalidByte { position: 3, found: 0 })
+    );
+
+    let err = StringSequence::read_from(&b"\x01\x01\x01\xc3"[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.into_inner().unwrap().downcast_ref::<DecodeError>(),
+        Some(&DecodeError::InvalidUtf8 { position: 3 })
+    );
+}
+
+#[test]
+fn aligned_bytes() {
+    use crate::binary::BinaryExt;
+    use crate::{DecodeError, StringSequenceRef};
+
+    // Copies into a buffer of `u64`, to keep the alignment.
+    fn aligned(bytes: &[u8]) -> (Vec<u64>, usize) {
+        let mut words = vec![0u64; bytes.len().div_ceil(8) + 1];
+        let dst =
+            unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, bytes.len()) };
+        dst.copy_from_slice(bytes);
+        (words, bytes.len())
+    }
+
+    fn view(buf: &(Vec<u64>, usize)) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(buf.0.as_ptr() as *const u8, buf.1) }
+    }
+
+    let seq = StringSequence::parse_delimited("/usr/local/\u{ff}/", '/');
+    for sub in [seq.slice_ref(..), seq.slice_ref(2..4), seq.slice_ref(1..1)] {
+        let bytes = sub.to_aligned_bytes();
+        let mut written = Vec::new();
+        sub.write_aligned_to(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        let buf = aligned(&bytes);
+        let borrowed = StringSequenceRef::from_aligned_bytes(view(&buf)).unwrap();
+        assert_eq!(borrowed, sub);
+        assert_eq!(borrowed.text(), sub.text());
+    }
+
+    let bytes = seq.to_aligned_bytes();
+    let width = std::mem::size_of::<crate::Offset>();
+    let decode = |patch: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = bytes.clone();
+        patch(&mut bytes);
+        StringSequenceRef::from_aligned_bytes(view(&aligned(&bytes))).map(StringSequence::from)
+    };
+
+    assert_eq!(decode(&|_| ()).unwrap(), seq);
+    assert_eq!(
+        decode(&|x| x[1] = b'!'),
+        Err(DecodeError::InvalidByte { position: 1, found: b'!' })
+    );
+    assert_eq!(decode(&|x| x[4] = 9), Err(DecodeError::UnsupportedVersion { version: 9 }));
+    assert_eq!(
+        decode(&|x| x[5] = 3),
+        Err(DecodeError::OffsetWidth { expected: width as u8, found: 3 })
+    );
+    assert_eq!(decode(&|x| x[7] = 1), Err(DecodeError::InvalidByte { position: 7, found: 1 }));
+    assert_eq!(decode(&|x| x.push(0)), Err(DecodeError::TrailingBytes { position: bytes.len() }));
+    assert_eq!(
+        decode(&|x| x.truncate(bytes.len() - 1)),
+        Err(DecodeError::UnexpectedEnd { position: bytes.len() - 1 })
+    );
+    assert_eq!(decode(&|x| x.truncate(20)), Err(DecodeError::UnexpectedEnd { position: 20 }));
+    assert_eq!(
+        decode(&|x| x[16 + 2 * width] = 0xff),
+        Err(DecodeError::InvalidOffset { position: 16 + 3 * width })
+    );
+    assert_eq!(decode(&|x| x[16] = 1), Err(DecodeError::InvalidOffset { position: 16 }));
+
+    // Splits `ÿ` in the middle.
+    let text_start = 16 + (seq.len() + 1) * width;
+    assert_eq!(
+        decode(&|x| x[16 + 4 * width] -= 1),
+        Err(DecodeError::InvalidUtf8 { position: text_start + 8 })
+    );
+
+    let buf = aligned(&[&[0u8][..], &bytes].concat());
+    assert_eq!(
+        StringSequenceRef::from_aligned_bytes(&view(&buf)[1..]),
+        Err(DecodeError::Misaligned { alignment: std::mem::align_of::<crate::Offset>() })
+    );
+}
+
+#[test]
+fn corpus() {
+    use crate::binary::AlignedBytes;
+    use crate::corpus::{Corpus, CorpusWriter};
+    use crate::DecodeError;
+
+    let tokens = ["", "a", "ab", "b", "\u{ff}"];
+    let random_seq = || {
+        StringSequence::from_iter(
+            (0..rand::random::<usize>() % 4)
+                .map(|_| tokens[rand::random::<usize>() % tokens.len()]),
+        )
+    };
+
+    let mut seqs: Vec<_> = (0..200).map(|_| random_seq()).collect();
+    seqs.sort();
+    seqs.dedup();
+
+    let mut writer = CorpusWriter::new();
+    for (i, seq) in seqs.iter().enumerate() {
+        assert_eq!(writer.push(seq), i);
+    }
+
+    let bytes = writer.to_bytes();
+    let mut written = Vec::new();
+    writer.write_to(&mut written).unwrap();
+    assert_eq!(written, bytes);
+
+    let buf = AlignedBytes::read_from(&bytes[..]).unwrap();
+    assert_eq!(&buf[..], &bytes[..]);
+
+    let corpus = Corpus::new(&buf).unwrap();
+    assert_eq!(corpus.len(), seqs.len());
+    assert!(corpus.iter().eq(seqs.iter().map(|x| x.as_seq_ref())));
+    assert!(corpus.iter().rev().eq(seqs.iter().rev().map(|x| x.as_seq_ref())));
+    assert!(corpus.get(seqs.len()).is_none());
+
+    let entry = corpus.get(seqs.len() - 1).unwrap();
+    let last = seqs.last().unwrap();
+    assert_eq!((entry.len(), entry.text()), (last.len(), last.text()));
+    assert!(entry.iter().rev().eq(last.iter().rev()));
+    assert_eq!(entry.get(entry.len()), None);
+    assert_eq!(&StringSequence::from(entry), last);
+
+    for (i, seq) in seqs.iter().enumerate() {
+        assert_eq!(corpus.binary_search(seq), Ok(i));
+    }
+
+    for _ in 0..100 {
+        let seq = random_seq();
+        assert_eq!(corpus.binary_search(&seq), seqs.binary_search(&seq));
+    }
+
+    let empty = AlignedBytes::from(&CorpusWriter::new().to_bytes()[..]);
+    let empty = Corpus::new(&empty).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.binary_search(&StringSequence::from_slice(&["a"])), Err(0));
+
+    // A single sequence of `["ab", "ÿ"]`, to corrupt.
+    let mut writer = CorpusWriter::new();
+    writer.push(&StringSequence::from_slice(&["ab", "\u{ff}"]));
+    let bytes = writer.to_bytes();
+    let index_start = 24 + 2 * 8;
+
+    // Boundaries are `u64` regardless of `Offset`.
+    assert_eq!(bytes.len(), index_start + 3 * 8 + 4);
+
+    let decode = |patch: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = bytes.clone();
+        patch(&mut bytes);
+        Corpus::new(&AlignedBytes::from(&bytes[..])).map(|x| x.len())
+    };
+
+    assert_eq!(decode(&|_| ()), Ok(1));
+    assert_eq!(
+        decode(&|x| x[0] = b'!'),
+        Err(DecodeError::InvalidByte { position: 0, found: b'!' })
+    );
+    assert_eq!(decode(&|x| x[5] = 4), Err(DecodeError::OffsetWidth { expected: 8, found: 4 }));
+    assert_eq!(decode(&|x| x.push(0)), Err(DecodeError::TrailingBytes { position: bytes.len() }));
+    assert_eq!(
+        decode(&|x| x.truncate(bytes.len() - 1)),
+        Err(DecodeError::UnexpectedEnd { position: bytes.len() - 1 })
+    );
+    assert_eq!(decode(&|x| x[32] = 3), Err(DecodeError::InvalidOffset { position: 32 }));
+    assert_eq!(decode(&|x| x[8] = 0), Err(DecodeError::InvalidOffset { position: 24 }));
+    for counts in [8..16, 16..24] {
+        assert_eq!(
+            decode(&|x| x[counts.clone()].fill(0xff)),
+            Err(DecodeError::UnexpectedEnd { position: bytes.len() })
+        );
+    }
+    assert_eq!(
+        decode(&|x| x[index_start + 8] = 3),
+        Err(DecodeError::InvalidUtf8 { position: index_start + 3 * 8 })
+    );
+
+    let buf = AlignedBytes::from(&[&[0u8][..], &bytes].concat()[..]);
+    assert_eq!(Corpus::new(&buf[1..]).unwrap_err(), DecodeError::Misaligned { alignment: 8 });
+}
+
+#[test]
+fn arena() {
+    use crate::arena::StringSequenceArena;
+
+    let tokens = ["", "a", "bc", "\u{ff}"];
+    let random_seq = || {
+        StringSequence::from_iter(
+            (0..rand::random::<usize>() % 4)
+                .map(|_| tokens[rand::random::<usize>() % tokens.len()]),
+        )
+    };
+
+    let seqs: Vec<_> = (0..100).map(|_| random_seq()).collect();
+    let mut arena = StringSequenceArena::with_capacity(seqs.len(), 0, 0);
+    let ids: Vec<_> = seqs.iter().map(|x| arena.push(x)).collect();
+
+    assert_eq!(arena.len(), seqs.len());
+    assert!(arena.ids().eq(ids.iter().copied()));
+    for (id, seq) in ids.iter().zip(&seqs) {
+        assert_eq!(&arena.get(*id).unwrap(), seq);
+    }
+
+    let frozen = arena.clone().freeze();
+    assert_eq!(frozen.len(), seqs.len());
+    for (id, seq) in ids.iter().zip(&seqs) {
+        assert_eq!(&frozen.get(*id).unwrap(), seq);
+        assert_eq!(&frozen.get_ref(*id).unwrap(), seq);
+    }
+
+    // Handed out sequences share the buffer, and outlive the arena.
+    let (a, b) = (frozen.get(ids[0]).unwrap(), frozen.get(ids[1]).unwrap());
+    let full = |x: &SharedStringSequence| x.clone().into_full_sequence();
+    assert!(SharedStringSequence::ptr_eq(&full(&a), &full(&b)));
+    drop(frozen);
+    assert!(a == seqs[0] && b == seqs[1]);
+
+    arena.clear();
+    assert!(arena.is_empty() && arena.get(ids[0]).is_none());
+    let id = arena.push_tokens(["x", "", "y"]);
+    assert_eq!(id.index(), 0);
+    assert_eq!(arena.get(id).unwrap(), ["x", "", "y"].into_iter().collect::<StringSequence>());
+    assert_eq!(arena.push(&MutableStringSequence::new()).index(), 1);
+}
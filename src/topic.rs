@@ -0,0 +1,290 @@
+//! MQTT-style topic filters and routing.
+
+use std::collections::BTreeMap;
+
+use crate::{SharedStringSequence, StringSequenceIter, StringSequenceView, TopicFilterError};
+
+/// Token which matches exactly one token of a topic.
+pub const SINGLE_LEVEL: &str = "+";
+
+/// Token which matches every remaining token of a topic, including none.
+pub const MULTI_LEVEL: &str = "#";
+
+/// Checks if a topic is reserved, i.e. its first token starts with `$`. Reserved topics are not
+/// matched by a wildcard at the first level.
+fn is_reserved<T: StringSequenceView + ?Sized>(topic: &T) -> bool {
+    topic.first().is_some_and(|x| x.starts_with('$'))
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           FILTER                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Validated topic filter, following MQTT semantics.
+///
+/// - `+` matches exactly one token.
+/// - `#` matches the remaining tail, including the parent itself; `a/#` matches `a`.
+/// - Topics whose first token starts with `$` are not matched by a wildcard at the first level.
+///
+/// ```
+/// use strseq::{topic::TopicFilter, SharedStringSequence};
+///
+/// let filter = TopicFilter::parse("sensor/+/temp/#", '/').unwrap();
+/// assert!(filter.matches(&SharedStringSequence::parse_delimited("sensor/3/temp", '/')));
+/// assert!(!filter.matches(&SharedStringSequence::parse_delimited("sensor/temp", '/')));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TopicFilter {
+    tokens: SharedStringSequence,
+}
+
+impl TopicFilter {
+    /// Validates given tokens as a filter.
+    pub fn new<T: StringSequenceView + ?Sized>(tokens: &T) -> Result<Self, TopicFilterError> {
+        if tokens.is_empty() {
+            return Err(TopicFilterError::Empty);
+        }
+
+        for (index, token) in tokens.iter().enumerate() {
+            match token {
+                MULTI_LEVEL if index + 1 != tokens.len() => {
+                    return Err(TopicFilterError::MisplacedTail { token: index })
+                }
+                SINGLE_LEVEL | MULTI_LEVEL => (),
+                _ if token.contains(['+', '#']) => {
+                    return Err(TopicFilterError::InvalidWildcard { token: index })
+                }
+                _ => (),
+            }
+        }
+
+        Ok(Self { tokens: tokens.into() })
+    }
+
+    /// Validates a text split by `delim` as a filter.
+    pub fn parse(text: &str, delim: char) -> Result<Self, TopicFilterError> {
+        Self::new(&SharedStringSequence::parse_delimited(text, delim))
+    }
+
+    pub fn as_sequence(&self) -> &SharedStringSequence {
+        &self.tokens
+    }
+
+    /// Checks if the filter contains any wildcard token.
+    pub fn has_wildcard(&self) -> bool {
+        self.tokens.iter().any(|x| x == SINGLE_LEVEL || x == MULTI_LEVEL)
+    }
+
+    /// Checks if the topic is matched by this filter.
+    pub fn matches<T: StringSequenceView + ?Sized>(&self, topic: &T) -> bool {
+        let mut filter = self.tokens.iter();
+        let mut topic_tokens = topic.iter();
+
+        if is_reserved(topic) && matches!(filter.clone().next(), Some(SINGLE_LEVEL | MULTI_LEVEL)) {
+            return false;
+        }
+
+        loop {
+            match (filter.next(), topic_tokens.next()) {
+                (Some(MULTI_LEVEL), _) => break true,
+                (Some(SINGLE_LEVEL), Some(_)) => (),
+                (Some(a), Some(b)) if a == b => (),
+                (None, None) => break true,
+                _ => break false,
+            }
+        }
+    }
+}
+
+impl From<TopicFilter> for SharedStringSequence {
+    fn from(value: TopicFilter) -> Self {
+        value.tokens
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           ROUTER                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Map from [`TopicFilter`] to a value, which finds every filter matching a published topic.
+///
+/// Filters are stored in a tree whose wildcard edges are kept apart from literal ones, so a lookup
+/// visits only branches which can match, rather than scanning every filter.
+///
+/// ```
+/// use strseq::{topic::{TopicFilter, TopicRouter}, SharedStringSequence};
+///
+/// let mut router = TopicRouter::new();
+/// router.insert(&TopicFilter::parse("home/+/light", '/').unwrap(), "lights");
+/// router.insert(&TopicFilter::parse("home/#", '/').unwrap(), "home");
+/// router.insert(&TopicFilter::parse("office/#", '/').unwrap(), "office");
+///
+/// let mut found = router.route(&SharedStringSequence::parse_delimited("home/kitchen/light", '/'));
+/// found.sort();
+/// assert_eq!(found, [&"home", &"lights"]);
+/// ```
+pub struct TopicRouter<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    /// Value of the filter which ends at this node.
+    value: Option<V>,
+    /// Value of the filter which ends with `#` after this node.
+    tail: Option<V>,
+    single: Option<Box<Node<V>>>,
+    children: BTreeMap<Box<str>, Node<V>>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self { value: None, tail: None, single: None, children: BTreeMap::new() }
+    }
+}
+
+impl<V> Node<V> {
+    fn is_empty(&self) -> bool {
+        self.value.is_none()
+            && self.tail.is_none()
+            && self.single.is_none()
+            && self.children.is_empty()
+    }
+
+    fn slot(&self, mut filter: StringSequenceIter<'_>) -> Option<&Option<V>> {
+        match filter.next() {
+            None => Some(&self.value),
+            Some(MULTI_LEVEL) => Some(&self.tail),
+            Some(SINGLE_LEVEL) => self.single.as_ref()?.slot(filter),
+            Some(token) => self.children.get(token)?.slot(filter),
+        }
+    }
+
+    fn slot_mut(&mut self, mut filter: StringSequenceIter<'_>) -> Option<&mut Option<V>> {
+        match filter.next() {
+            None => Some(&mut self.value),
+            Some(MULTI_LEVEL) => Some(&mut self.tail),
+            Some(SINGLE_LEVEL) => self.single.as_mut()?.slot_mut(filter),
+            Some(token) => self.children.get_mut(token)?.slot_mut(filter),
+        }
+    }
+
+    fn slot_or_create(&mut self, mut filter: StringSequenceIter<'_>) -> &mut Option<V> {
+        match filter.next() {
+            None => &mut self.value,
+            Some(MULTI_LEVEL) => &mut self.tail,
+            Some(SINGLE_LEVEL) => {
+                self.single.get_or_insert_with(Default::default).slot_or_create(filter)
+            }
+            Some(token) => self.children.entry(token.into()).or_default().slot_or_create(filter),
+        }
+    }
+
+    /// Takes the value of the filter, pruning nodes which become empty.
+    fn take(&mut self, mut filter: StringSequenceIter<'_>) -> Option<V> {
+        match filter.next() {
+            None => self.value.take(),
+            Some(MULTI_LEVEL) => self.tail.take(),
+            Some(SINGLE_LEVEL) => {
+                let child = self.single.as_mut()?;
+                let value = child.take(filter);
+
+                if child.is_empty() {
+                    self.single = None;
+                }
+
+                value
+            }
+            Some(token) => {
+                let child = self.children.get_mut(token)?;
+                let value = child.take(filter);
+
+                if child.is_empty() {
+                    self.children.remove(token);
+                }
+
+                value
+            }
+        }
+    }
+
+    /// Collects values of every filter matching the remaining topic. Wildcards are skipped if
+    /// `reserved`, which is set only at the first level.
+    fn collect<'a>(
+        &'a self,
+        mut topic: StringSequenceIter<'_>,
+        reserved: bool,
+        out: &mut Vec<&'a V>,
+    ) {
+        if !reserved {
+            out.extend(&self.tail);
+        }
+
+        let Some(token) = topic.next() else {
+            out.extend(&self.value);
+            return;
+        };
+
+        if let Some(child) = self.children.get(token) {
+            child.collect(topic.clone(), false, out);
+        }
+
+        if let Some(child) = self.single.as_ref().filter(|_| !reserved) {
+            child.collect(topic, false, out);
+        }
+    }
+}
+
+impl<V> Default for TopicRouter<V> {
+    fn default() -> Self {
+        Self { root: Node::default(), len: 0 }
+    }
+}
+
+impl<V> TopicRouter<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored filters.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Inserts a value for the filter, returning the previous one of the same filter.
+    pub fn insert(&mut self, filter: &TopicFilter, value: V) -> Option<V> {
+        let prev = self.root.slot_or_create(filter.tokens.iter()).replace(value);
+        self.len += prev.is_none() as usize;
+        prev
+    }
+
+    /// Value of the filter itself. Use [`Self::route`] to find filters matching a topic.
+    pub fn get(&self, filter: &TopicFilter) -> Option<&V> {
+        self.root.slot(filter.tokens.iter())?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, filter: &TopicFilter) -> Option<&mut V> {
+        self.root.slot_mut(filter.tokens.iter())?.as_mut()
+    }
+
+    pub fn remove(&mut self, filter: &TopicFilter) -> Option<V> {
+        let value = self.root.take(filter.tokens.iter());
+        self.len -= value.is_some() as usize;
+        value
+    }
+
+    /// Values of every filter matching the published topic, in no particular order.
+    pub fn route<T: StringSequenceView + ?Sized>(&self, topic: &T) -> Vec<&V> {
+        let mut out = Vec::new();
+        self.root.collect(topic.iter(), is_reserved(topic), &mut out);
+        out
+    }
+}
@@ -0,0 +1,328 @@
+//! Prefix tree keyed by string sequences.
+
+use std::collections::{btree_map, BTreeMap};
+
+use crate::{MutableStringSequence, SharedStringSequence, StringSequenceRef, StringSequenceView};
+
+/// Map keyed by string sequences, where each token is an edge of the tree. Supports prefix
+/// queries such as [`Self::longest_prefix_match`] and [`Self::iter_prefix`].
+///
+/// Keys can be any sequence view; returned keys are [`SharedStringSequence`]s. Iteration order
+/// is the same as the `Ord` of sequences.
+///
+/// ```
+/// use strseq::{trie::SequenceTrie, StringSequence};
+///
+/// let mut trie = SequenceTrie::new();
+/// trie.insert(&StringSequence::parse_delimited("api/v1", '/'), "v1");
+/// trie.insert(&StringSequence::parse_delimited("api/v1/users", '/'), "users");
+///
+/// let (key, value) =
+///     trie.longest_prefix_match(&StringSequence::parse_delimited("api/v1/posts/3", '/')).unwrap();
+/// assert_eq!((key.text(), *value), ("apiv1", "v1"));
+/// ```
+pub struct SequenceTrie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<Box<str>, Node<V>>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self { value: None, children: BTreeMap::new() }
+    }
+}
+
+impl<V: Clone> Clone for Node<V> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), children: self.children.clone() }
+    }
+}
+
+impl<V> Node<V> {
+    fn find<T: StringSequenceView + ?Sized>(&self, key: &T) -> Option<&Self> {
+        key.iter().try_fold(self, |node, token| node.children.get(token))
+    }
+
+    fn find_mut<T: StringSequenceView + ?Sized>(&mut self, key: &T) -> Option<&mut Self> {
+        key.iter().try_fold(self, |node, token| node.children.get_mut(token))
+    }
+
+    fn find_or_create<T: StringSequenceView + ?Sized>(&mut self, key: &T) -> &mut Self {
+        key.iter().fold(self, |node, token| node.children.entry(token.into()).or_default())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.value.is_none() && self.children.is_empty()
+    }
+
+    fn count(&self) -> usize {
+        self.value.is_some() as usize + self.children.values().map(Self::count).sum::<usize>()
+    }
+
+    /// Detaches the node at the end of `tokens`, pruning nodes which become empty.
+    fn detach(&mut self, tokens: &mut crate::StringSequenceIter) -> Option<Self> {
+        let Some(token) = tokens.next() else { return Some(std::mem::take(self)) };
+
+        let child = self.children.get_mut(token)?;
+        let detached = child.detach(tokens);
+
+        if child.is_empty() {
+            self.children.remove(token);
+        }
+
+        detached
+    }
+
+    /// Takes the value at the end of `tokens`, pruning nodes which become empty.
+    fn take(&mut self, tokens: &mut crate::StringSequenceIter) -> Option<V> {
+        let Some(token) = tokens.next() else { return self.value.take() };
+
+        let child = self.children.get_mut(token)?;
+        let value = child.take(tokens);
+
+        if child.is_empty() {
+            self.children.remove(token);
+        }
+
+        value
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                           METHODS                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+impl<V> Default for SequenceTrie<V> {
+    fn default() -> Self {
+        Self { root: Node::default(), len: 0 }
+    }
+}
+
+impl<V: Clone> Clone for SequenceTrie<V> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<V> SequenceTrie<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Inserts a value, returning the previous one at the same key.
+    pub fn insert<T: StringSequenceView + ?Sized>(&mut self, key: &T, value: V) -> Option<V> {
+        let prev = self.root.find_or_create(key).value.replace(value);
+        self.len += prev.is_none() as usize;
+        prev
+    }
+
+    pub fn get<T: StringSequenceView + ?Sized>(&self, key: &T) -> Option<&V> {
+        self.root.find(key)?.value.as_ref()
+    }
+
+    pub fn get_mut<T: StringSequenceView + ?Sized>(&mut self, key: &T) -> Option<&mut V> {
+        self.root.find_mut(key)?.value.as_mut()
+    }
+
+    pub fn contains_key<T: StringSequenceView + ?Sized>(&self, key: &T) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the value at the key. Descendants of the key are kept.
+    pub fn remove<T: StringSequenceView + ?Sized>(&mut self, key: &T) -> Option<V> {
+        let value = self.root.take(&mut key.iter());
+        self.len -= value.is_some() as usize;
+        value
+    }
+
+    /// Removes the value at `prefix` and every value under it. Removed values are returned as a
+    /// new trie, whose keys are relative to `prefix`.
+    pub fn remove_subtree<T: StringSequenceView + ?Sized>(&mut self, prefix: &T) -> Self {
+        let root = self.root.detach(&mut prefix.iter()).unwrap_or_default();
+        let len = root.count();
+        self.len -= len;
+        Self { root, len }
+    }
+
+    /// Gets the entry of the key for in-place manipulation.
+    pub fn entry<'a, 'k, T: StringSequenceView + ?Sized>(
+        &'a mut self,
+        key: &'k T,
+    ) -> Entry<'a, 'k, V> {
+        Entry { trie: self, key: key.as_seq_ref() }
+    }
+
+    /// Finds the longest prefix of `key` which has a value, and returns it with the value.
+    pub fn longest_prefix_match<T: StringSequenceView + ?Sized>(
+        &self,
+        key: &T,
+    ) -> Option<(SharedStringSequence, &V)> {
+        let mut node = &self.root;
+        let mut found = node.value.as_ref().map(|x| (0, x));
+
+        for (index, token) in key.iter().enumerate() {
+            let Some(child) = node.children.get(token) else { break };
+            node = child;
+
+            if let Some(value) = &node.value {
+                found = Some((index + 1, value));
+            }
+        }
+
+        found.map(|(len, value)| (SharedStringSequence::from(&key.slice_ref(..len)), value))
+    }
+
+    /// Iterates over every key-value pair.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(MutableStringSequence::new(), Some(&self.root))
+    }
+
+    /// Iterates over key-value pairs of `prefix` itself and every key under it.
+    pub fn iter_prefix<T: StringSequenceView + ?Sized>(&self, prefix: &T) -> Iter<'_, V> {
+        Iter::new(MutableStringSequence::from(prefix), self.root.find(prefix))
+    }
+}
+
+impl<V: std::fmt::Debug> std::fmt::Debug for SequenceTrie<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a SequenceTrie<V> {
+    type Item = (SharedStringSequence, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: StringSequenceView, V> Extend<(K, V)> for SequenceTrie<V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| drop(self.insert(&key, value)));
+    }
+}
+
+impl<K: StringSequenceView, V> FromIterator<(K, V)> for SequenceTrie<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                            ENTRY                                           */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Entry of a [`SequenceTrie`]. Nodes are created only when a value is inserted.
+pub struct Entry<'a, 'k, V> {
+    trie: &'a mut SequenceTrie<V>,
+    key: StringSequenceRef<'k>,
+}
+
+impl<'a, V> Entry<'a, '_, V> {
+    pub fn key(&self) -> StringSequenceRef<'_> {
+        self.key
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V {
+        let slot = &mut self.trie.root.find_or_create(&self.key).value;
+        if slot.is_none() {
+            self.trie.len += 1;
+        }
+
+        slot.get_or_insert_with(default)
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+        if let Some(value) = self.trie.root.find_mut(&self.key).and_then(|x| x.value.as_mut()) {
+            f(value);
+        }
+
+        self
+    }
+}
+
+/* ------------------------------------------------------------------------------------------ */
+/*                                          ITERATOR                                          */
+/* ------------------------------------------------------------------------------------------ */
+
+/// Pre-order iterator over a [`SequenceTrie`].
+pub struct Iter<'a, V> {
+    path: MutableStringSequence,
+    pending: Option<&'a V>,
+    stack: Vec<btree_map::Iter<'a, Box<str>, Node<V>>>,
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn new(path: MutableStringSequence, root: Option<&'a Node<V>>) -> Self {
+        Self {
+            path,
+            pending: root.and_then(|x| x.value.as_ref()),
+            stack: root.map(|x| x.children.iter()).into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (SharedStringSequence, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.pending.take() {
+            return Some((SharedStringSequence::from(&self.path), value));
+        }
+
+        loop {
+            let Some((token, node)) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+
+                // Every frame except the bottom one has pushed a token.
+                if !self.stack.is_empty() {
+                    self.path.pop_back();
+                }
+
+                continue;
+            };
+
+            self.path.push_back(token);
+            self.stack.push(node.children.iter());
+
+            if let Some(value) = &node.value {
+                return Some((SharedStringSequence::from(&self.path), value));
+            }
+        }
+    }
+}
+
+impl<V> std::iter::FusedIterator for Iter<'_, V> {}
@@ -1,19 +1,26 @@
-use std::{ops::Range, slice::from_raw_parts, sync::Arc};
+use std::{
+    borrow::Borrow,
+    ops::Range,
+    slice::from_raw_parts,
+    sync::{Arc, Weak},
+};
 
-use crate::base_trait::{up, StringSequenceView, ToRange};
+use crate::base_trait::{text_range, up, Offset, RawView, StringSequenceView, ToRange};
 
 /* ----------------------------------------- Common ----------------------------------------- */
 #[inline]
-fn as_inner(slice: &[[u32; 2]], text_start_index: usize) -> (&[u8], &[Range<u32>]) {
+fn as_inner(slice: &[Offset], text_start_index: usize) -> (&[u8], &[Offset]) {
     let (index, buffer) = slice.split_at(text_start_index);
+    let buffer_len = index.last().copied().unwrap_or(0) as usize;
 
     // SAFETY: Plain POD conversion
-    unsafe {
-        let index = from_raw_parts(index.as_ptr() as *const Range<u32>, index.len());
-        let buffer_len = index.last().map(|x| x.end).unwrap_or(0) as usize;
+    unsafe { (from_raw_parts(buffer.as_ptr() as *const u8, buffer_len), index) }
+}
 
-        (from_raw_parts(buffer.as_ptr() as *const u8, buffer_len), index)
-    }
+/// Copies text and boundaries of given view, rebasing boundaries to start from zero.
+pub(crate) fn rebased_inner<'a>(text: &'a [u8], index: &[Offset]) -> (Vec<Offset>, &'a [u8]) {
+    let base = index[0];
+    (index.iter().map(|x| x - base).collect(), &text[up(text_range(index))])
 }
 
 /* ------------------------------------------------------------------------------------------ */
@@ -22,7 +29,7 @@ fn as_inner(slice: &[[u32; 2]], text_start_index: usize) -> (&[u8], &[Range<u32>
 
 #[derive(Clone)]
 pub struct StringSequence {
-    raw: Box<[[u32; 2]]>, // To keep the original alignment of the buffer
+    raw: Box<[Offset]>, // To keep the original alignment of the buffer
     index_count: usize,
 }
 
@@ -31,50 +38,66 @@ impl_seq_view!(StringSequence);
 impl StringSequence {
     /// Extends given index buffer by text buffer, to make compact representation of a sequence of
     /// strings. If `index_buf` reserved enough capacity, this function will not allocate.
-    pub(crate) fn from_owned_index(index_buf: Vec<Range<u32>>, text: &[u8]) -> Self {
-        // SAFETY: Conversion from `Vec<(u32, u32)>` to `Vec<u64>`:
-        //  - Both are plain old data
-        //    - Though `Range<u32>` doesn't define `Copy` trait, that is due to design decision, not
-        //      because it has non-trivial logic for cloning.
-        //  - Element size is the same
-        //  - Destination type's memory alignment is more permissive
-        let mut raw: Vec<[u32; 2]> = unsafe { std::mem::transmute(index_buf) };
+    ///
+    /// `index_buf` should be the boundaries of tokens in `text`, which starts from zero.
+    pub(crate) fn from_owned_index(index_buf: Vec<Offset>, text: &[u8]) -> Self {
+        debug_assert!(index_buf.first() == Some(&0));
+        debug_assert!(index_buf.last().map(|x| *x as usize) == Some(text.len()));
 
+        let mut raw = index_buf;
         let text_start_index = raw.len();
-        raw.reserve_exact((text.len() + 7) / 8);
-
-        // SAFETY: It's just plain old data.
-        let (beg, mid, end) = unsafe { text.align_to::<[u32; 2]>() };
-        debug_assert!(beg.is_empty());
 
-        raw.extend_from_slice(mid);
+        let num_text_words = text.len().div_ceil(std::mem::size_of::<Offset>());
+        raw.reserve_exact(num_text_words);
+        raw.resize(text_start_index + num_text_words, 0);
 
-        let from_slice = |slice: &[u8]| {
-            u32::from_ne_bytes(std::array::from_fn(|index| {
-                slice.get(index).copied().unwrap_or_default()
-            }))
-        };
-
-        // Push remaining bytes
-        raw.push([from_slice(end), from_slice(&end[4.min(end.len())..])]);
+        // SAFETY: It's just plain old data, and we've reserved enough space to copy into.
+        unsafe {
+            let dst = raw[text_start_index..].as_mut_ptr() as *mut u8;
+            std::ptr::copy_nonoverlapping(text.as_ptr(), dst, text.len());
+        }
 
         raw.shrink_to_fit();
 
         Self { raw: raw.into_boxed_slice(), index_count: text_start_index }
     }
+
+    /// Allocates a compact buffer of exact size, then lets `fill` write `num_tokens + 1`
+    /// boundaries and `text_len` bytes of text into it.
+    ///
+    /// # Safety
+    ///
+    /// `fill` must write boundaries which start from zero, are non-decreasing and end at
+    /// `text_len`, and the text should be valid UTF-8 split at character boundaries.
+    pub(crate) unsafe fn from_layout(
+        num_tokens: usize,
+        text_len: usize,
+        fill: impl FnOnce(&mut [Offset], &mut [u8]),
+    ) -> Self {
+        let index_count = num_tokens + 1;
+        let num_text_words = text_len.div_ceil(std::mem::size_of::<Offset>());
+        let mut raw = vec![0 as Offset; index_count + num_text_words].into_boxed_slice();
+
+        let (index, text) = raw.split_at_mut(index_count);
+        let text = std::slice::from_raw_parts_mut(text.as_mut_ptr() as *mut u8, text_len);
+        fill(index, text);
+
+        Self { raw, index_count }
+    }
 }
 
 /* --------------------------------------- Conversion --------------------------------------- */
 
-impl<'a, T: StringSequenceView> From<&'a T> for StringSequence {
+impl<'a, T: StringSequenceView + ?Sized> From<&'a T> for StringSequence {
     fn from(value: &'a T) -> Self {
         let (buffer, index) = value.inner();
-        Self::from_owned_index(index.to_vec(), buffer)
+        let (index, text) = rebased_inner(buffer, index);
+        Self::from_owned_index(index, text)
     }
 }
 
-impl StringSequenceView for StringSequence {
-    fn inner(&self) -> (&[u8], &[Range<u32>]) {
+impl RawView for StringSequence {
+    fn inner(&self) -> (&[u8], &[Offset]) {
         as_inner(&self.raw, self.index_count)
     }
 }
@@ -88,52 +111,174 @@ impl StringSequenceView for StringSequence {
 /// It can represent only a subsequence of the original sequence.
 #[derive(Clone)]
 pub struct SharedStringSequence {
-    raw: Arc<[[u32; 2]]>,
+    raw: Arc<[Offset]>,
     index_count: usize,
-    token_range: Range<u32>, // Naively expect we won't store more than 2^32 tokens.
+    token_range: Range<Offset>, // Number of tokens is limited by `Offset` as well.
+    #[cfg(feature = "cached-hash")]
+    full_hash: u64, // Fingerprint of the whole buffer, computed once on creation.
 }
 
 impl_seq_view!(SharedStringSequence);
 
 impl SharedStringSequence {
+    /// Subsequence of given range of tokens of the whole buffer, regardless of the range `self`
+    /// currently covers. The buffer is shared with `self`. See [`Self::narrow`] for a range
+    /// relative to `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
     pub fn subsequence(&self, range: impl ToRange) -> Self {
-        let range = range.to_range(self.index_count);
-        Self { token_range: range.start as _..range.end as _, ..self.clone() }
+        let num_tokens = self.index_count - 1;
+        let range = range.to_range(num_tokens);
+        assert!(range.start <= range.end && range.end <= num_tokens, "range out of bounds");
+
+        Self { token_range: range.start as Offset..range.end as Offset, ..self.clone() }
+    }
+
+    /// Narrows this sequence into given range of tokens, which is relative to current
+    /// subsequence. The buffer is shared with `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn narrow(&self, range: impl ToRange) -> Self {
+        let range = range.to_range(self.len());
+        assert!(range.start <= range.end && range.end <= self.len(), "range out of bounds");
+
+        let start = self.token_range.start;
+        Self {
+            token_range: start + range.start as Offset..start + range.end as Offset,
+            ..self.clone()
+        }
+    }
+
+    /// Divides this sequence into two at given token index, sharing the buffer. See
+    /// [`slice::split_at`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        (self.narrow(..mid), self.narrow(mid..))
+    }
+
+    /// Longest common prefix of given sequences, as a subsequence of the first one which shares
+    /// its buffer. Returns `None` if there's no sequence. See
+    /// [`StringSequenceRef::common_prefix`](crate::StringSequenceRef::common_prefix) for other
+    /// sequence types.
+    pub fn common_prefix<I>(seqs: I) -> Option<Self>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Self>,
+    {
+        let mut seqs = seqs.into_iter();
+        let first = seqs.next()?.borrow().clone();
+        let len = seqs.fold(first.len(), |len, x| len.min(first.common_prefix_len(x.borrow())));
+        Some(first.narrow(..len))
+    }
+
+    /// Longest common suffix of given sequences, as a subsequence of the first one which shares
+    /// its buffer. Returns `None` if there's no sequence. See
+    /// [`StringSequenceRef::common_suffix`](crate::StringSequenceRef::common_suffix) for other
+    /// sequence types.
+    pub fn common_suffix<I>(seqs: I) -> Option<Self>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Self>,
+    {
+        let mut seqs = seqs.into_iter();
+        let first = seqs.next()?.borrow().clone();
+        let len = seqs.fold(first.len(), |len, x| len.min(first.common_suffix_len(x.borrow())));
+        Some(first.narrow(first.len() - len..))
     }
 
     pub fn into_full_sequence(self) -> Self {
-        Self { token_range: 0..self.index_count as _, ..self }
+        Self { token_range: 0..self.index_count as Offset - 1, ..self }
+    }
+
+    /// Checks if both are the same subsequence of the same buffer, which implies equality.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.raw, &b.raw) && a.token_range == b.token_range
+    }
+
+    /// Checks if this sequence covers its whole buffer.
+    pub(crate) fn is_full_sequence(&self) -> bool {
+        self.token_range == (0..self.index_count as Offset - 1)
+    }
+
+    /// Weak reference to this sequence, which doesn't keep the buffer alive.
+    pub(crate) fn downgrade(&self) -> WeakSequence {
+        WeakSequence {
+            raw: Arc::downgrade(&self.raw),
+            index_count: self.index_count,
+            token_range: self.token_range.clone(),
+            #[cfg(feature = "cached-hash")]
+            full_hash: self.full_hash,
+        }
     }
 }
 
-impl StringSequenceView for SharedStringSequence {
-    fn inner(&self) -> (&[u8], &[Range<u32>]) {
+/// Weak counterpart of [`SharedStringSequence`], as [`Weak`] is for [`Arc`].
+#[derive(Debug, Clone)]
+pub(crate) struct WeakSequence {
+    raw: Weak<[Offset]>,
+    index_count: usize,
+    token_range: Range<Offset>,
+    #[cfg(feature = "cached-hash")]
+    full_hash: u64,
+}
+
+impl WeakSequence {
+    /// The sequence, if any [`SharedStringSequence`] still holds the buffer.
+    pub(crate) fn upgrade(&self) -> Option<SharedStringSequence> {
+        Some(SharedStringSequence {
+            raw: self.raw.upgrade()?,
+            index_count: self.index_count,
+            token_range: self.token_range.clone(),
+            #[cfg(feature = "cached-hash")]
+            full_hash: self.full_hash,
+        })
+    }
+
+    pub(crate) fn is_dead(&self) -> bool {
+        self.raw.strong_count() == 0
+    }
+}
+
+impl RawView for SharedStringSequence {
+    fn inner(&self) -> (&[u8], &[Offset]) {
         let (text, index) = as_inner(&self.raw, self.index_count);
-        (text, &index[up(self.token_range.clone())])
+        (text, &index[self.token_range.start as usize..=self.token_range.end as usize])
     }
 
-    fn text(&self) -> &str {
-        let (text, buffer) = self.inner();
-        let start = buffer.first().map(|x| x.start).unwrap_or(0) as usize;
-        let end = buffer.last().map(|x| x.end).unwrap_or(0) as usize;
+    /// O(1) for the whole buffer, which is the common case of map keys. Subsequences are hashed
+    /// on every call.
+    #[cfg(feature = "cached-hash")]
+    fn fingerprint(&self) -> u64 {
+        if self.is_full_sequence() {
+            return self.full_hash;
+        }
 
-        unsafe { std::str::from_utf8_unchecked(&text[start..end]) }
+        let (buffer, index) = self.inner();
+        crate::base_trait::fingerprint(buffer, index)
     }
 }
 
 impl From<StringSequence> for SharedStringSequence {
     fn from(value: StringSequence) -> Self {
         Self {
+            #[cfg(feature = "cached-hash")]
+            full_hash: crate::base_trait::RawView::fingerprint(&value),
             raw: value.raw.into(),
             index_count: value.index_count,
-            token_range: 0..value.index_count as _,
+            token_range: 0..value.index_count as Offset - 1,
         }
     }
 }
 
-impl<'a, T: StringSequenceView> From<&'a T> for SharedStringSequence {
+impl<'a, T: StringSequenceView + ?Sized> From<&'a T> for SharedStringSequence {
     fn from(value: &'a T) -> Self {
-        let (buffer, index) = value.inner();
-        StringSequence::from_owned_index(index.to_vec(), buffer).into()
+        StringSequence::from(value).into()
     }
 }